@@ -0,0 +1,14 @@
+#![no_main]
+
+use bilicli_napi::api::live::Packet;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    // any input must produce packets or an error, never a panic
+    if let Ok(packets) = Packet::decode_all(data) {
+        for packet in packets {
+            let _ = packet.json();
+            let _ = packet.popularity();
+        }
+    }
+});
@@ -13,12 +13,45 @@ export const enum MsgType {
   SuperChat = 1,
   Gift = 2,
   GuardBuy = 3,
-  UserAction = 4
+  UserAction = 4,
+  Disconnect = 5
 }
 export declare function restoreTerminal(): void
 export declare class Tui {
-  constructor(roomId: number, cookie?: string | undefined | null)
+  constructor(room: string, cookie?: string | undefined | null)
+  /** Open the live room of the streamer with the given UID. */
+  static withStreamerUid(uid: number, cookie?: string | undefined | null): Tui
+  /**
+   * Search streamers by name and open the live room of the match, letting the user
+   * pick one in the TUI when several streamers match.
+   */
+  static withStreamerName(name: string, cookie?: string | undefined | null): Tui
   get state(): Promise<AppState>
+  /** the proxy every request and the danmaku connection go through, without credentials */
+  get proxy(): Promise<string | null>
+  /** live commands received by `listen` that have no mapping yet, with their counts */
+  get unknownCommands(): Promise<Record<string, number>>
+  /**
+   * Resolve the room given to the constructor (short id, long id or url) to the
+   * canonical room id, or `null` if it cannot be resolved.
+   */
+  resolveRoom(): Promise<number | null>
+  /**
+   * Wait until the room has been resolved, which may need the user to pick a streamer
+   * or retry in the TUI, and return the canonical room id, or `null` if the TUI quit first.
+   */
+  waitRoom(): Promise<number | null>
+  /** Override the danmaku length limit, which otherwise follows the account's level. */
+  setInputLimit(limit?: number | undefined | null): Promise<void>
+  /**
+   * Route every request and the danmaku connection through an `http://`, `https://`,
+   * `socks5://` or `socks5h://` proxy, optionally with `user:password@`. Without a url
+   * the proxy is read from `ALL_PROXY`, `HTTPS_PROXY` or `HTTP_PROXY`, skipping the
+   * hosts in `NO_PROXY`. An invalid url is an error.
+   */
+  setProxy(url?: string | undefined | null): Promise<void>
+  /** Split messages longer than the limit into several danmaku, toggled with Ctrl+T. */
+  setSplitLongDanmu(split: boolean): Promise<void>
   /**
    * # Safety
    * This function is marked as unsafe because it requires exclusive access to the state.
@@ -29,6 +62,14 @@ export declare class Tui {
    * This function is marked as unsafe because it requires exclusive access to the state.
    */
   stop(): Promise<void>
+  /**
+   * Fetch the room's danmaku hosts, connect to them natively and feed the events
+   * into the state, instead of pushing them through `send_msg`.
+   *
+   * # Safety
+   * This function is marked as unsafe because it requires exclusive access to the state.
+   */
+  listen(uid?: number | undefined | null): Promise<void>
   /**
    * # Safety
    * This function is marked as unsafe because it requires exclusive access to the state.
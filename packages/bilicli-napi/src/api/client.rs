@@ -0,0 +1,353 @@
+use std::time::Duration;
+
+use reqwest::{header::COOKIE, RequestBuilder};
+use serde::{de::DeserializeOwned, Deserialize};
+use serde_json::Value;
+
+use super::{ApiError, ApiResult, ProxyConfig};
+
+pub const API_BASE_URL: &str = "https://api.live.bilibili.com";
+
+/// the main site API, used for endpoints that are not part of the live API such as search
+pub const MAIN_API_BASE_URL: &str = "https://api.bilibili.com";
+
+pub const USER_AGENT: &str = "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/127.0.0.0 Safari/537.36";
+
+const TIMEOUT: Duration = Duration::from_secs(10);
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// a shared client for every live API call, reusing one connection pool
+#[derive(Clone, Debug)]
+pub struct ApiClient {
+    client: reqwest::Client,
+    base_url: String,
+    main_base_url: String,
+    cookie: Option<String>,
+    proxy: Option<ProxyConfig>,
+    proxy_error: Option<String>,
+}
+
+impl ApiClient {
+    pub fn new(cookie: Option<String>) -> Self {
+        Self::builder().cookie(cookie).build()
+    }
+
+    pub fn builder() -> ApiClientBuilder {
+        ApiClientBuilder::default()
+    }
+
+    pub fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    pub fn main_base_url(&self) -> &str {
+        &self.main_base_url
+    }
+
+    pub fn cookie(&self) -> Option<&str> {
+        self.cookie.as_deref()
+    }
+
+    /// the proxy every request goes through, if any
+    pub fn proxy(&self) -> Option<&ProxyConfig> {
+        self.proxy.as_ref()
+    }
+
+    /// why the configured proxy cannot be used, in which case every request is refused
+    pub fn proxy_error(&self) -> Option<&str> {
+        self.proxy_error.as_deref()
+    }
+
+    /// the `bili_jct` value of the cookie, required as csrf token by every write endpoint
+    pub fn csrf(&self) -> Option<&str> {
+        self.cookie()?
+            .split(';')
+            .map(str::trim)
+            .find_map(|kv| kv.strip_prefix("bili_jct="))
+            .filter(|csrf| !csrf.is_empty())
+    }
+
+    /// the uid of the logged in account, taken from the `DedeUserID` value of the cookie
+    pub fn uid(&self) -> Option<u64> {
+        self.cookie()?
+            .split(';')
+            .map(str::trim)
+            .find_map(|kv| kv.strip_prefix("DedeUserID="))?
+            .parse()
+            .ok()
+    }
+
+    pub(crate) fn get(&self, path: &str) -> RequestBuilder {
+        self.with_cookie(self.client.get(format!("{}{}", self.base_url, path)))
+    }
+
+    pub(crate) fn post(&self, path: &str) -> RequestBuilder {
+        self.with_cookie(self.client.post(format!("{}{}", self.base_url, path)))
+    }
+
+    pub(crate) fn get_main(&self, path: &str) -> RequestBuilder {
+        self.with_cookie(self.client.get(format!("{}{}", self.main_base_url, path)))
+    }
+
+    /// send `request` and unwrap the `{ code, message, data }` envelope every endpoint shares
+    pub(crate) async fn send<T: DeserializeOwned>(&self, request: RequestBuilder) -> ApiResult<T> {
+        if let Some(reason) = &self.proxy_error {
+            return Err(ApiError::ProxyUnusable(reason.clone()));
+        }
+
+        let response = request.send().await.map_err(|err| match &self.proxy {
+            Some(proxy)
+                if err.is_connect()
+                    && !err
+                        .url()
+                        .and_then(|url| url.host_str())
+                        .is_some_and(|host| proxy.bypasses(host)) =>
+            {
+                ApiError::proxy(proxy, &err)
+            }
+            _ => err.into(),
+        })?;
+        if !response.status().is_success() {
+            return Err(ApiError::Status(response.status()));
+        }
+
+        let body = response.bytes().await?;
+        let envelope = serde_json::from_slice::<Envelope>(&body)?;
+        if envelope.code != 0 {
+            return Err(ApiError::business(envelope.code, envelope.message));
+        }
+
+        Ok(serde_json::from_value(envelope.data)?)
+    }
+
+    /// fail early with the same error the server would return when no cookie is set
+    pub(crate) fn require_csrf(&self) -> ApiResult<&str> {
+        if self.cookie.is_none() {
+            return Err(ApiError::business(-101, "未登录"));
+        }
+
+        self.csrf()
+            .ok_or_else(|| ApiError::business(-111, "无法找到 csrf token"))
+    }
+
+    fn with_cookie(&self, request: RequestBuilder) -> RequestBuilder {
+        match &self.cookie {
+            Some(cookie) => request.header(COOKIE, cookie),
+            None => request,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct Envelope {
+    code: i32,
+    #[serde(default)]
+    message: String,
+    #[serde(default)]
+    data: Value,
+}
+
+impl Default for ApiClient {
+    fn default() -> Self {
+        Self::new(None)
+    }
+}
+
+#[derive(Debug)]
+pub struct ApiClientBuilder {
+    base_url: String,
+    main_base_url: String,
+    cookie: Option<String>,
+    proxy: Option<ProxyConfig>,
+    proxy_error: Option<String>,
+    user_agent: String,
+    timeout: Duration,
+    connect_timeout: Duration,
+}
+
+impl Default for ApiClientBuilder {
+    fn default() -> Self {
+        Self {
+            base_url: API_BASE_URL.to_string(),
+            main_base_url: MAIN_API_BASE_URL.to_string(),
+            cookie: None,
+            proxy: None,
+            proxy_error: None,
+            user_agent: USER_AGENT.to_string(),
+            timeout: TIMEOUT,
+            connect_timeout: CONNECT_TIMEOUT,
+        }
+    }
+}
+
+impl ApiClientBuilder {
+    pub fn base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.base_url = base_url.into().trim_end_matches('/').to_string();
+        self
+    }
+
+    pub fn main_base_url(mut self, main_base_url: impl Into<String>) -> Self {
+        self.main_base_url = main_base_url.into().trim_end_matches('/').to_string();
+        self
+    }
+
+    pub fn cookie(mut self, cookie: Option<String>) -> Self {
+        self.cookie = cookie.filter(|cookie| !cookie.is_empty());
+        self
+    }
+
+    /// route every request through `proxy` instead of connecting directly
+    pub fn proxy(mut self, proxy: Option<ProxyConfig>) -> Self {
+        self.proxy = proxy;
+        self
+    }
+
+    /// refuse every request with `reason` instead of connecting directly, for a proxy that
+    /// was asked for but cannot be used
+    pub fn proxy_error(mut self, reason: Option<String>) -> Self {
+        self.proxy_error = reason;
+        self
+    }
+
+    pub fn user_agent(mut self, user_agent: impl Into<String>) -> Self {
+        self.user_agent = user_agent.into();
+        self
+    }
+
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    pub fn connect_timeout(mut self, connect_timeout: Duration) -> Self {
+        self.connect_timeout = connect_timeout;
+        self
+    }
+
+    /// build the client
+    ///
+    /// # Panics
+    /// if the http client cannot be built, see `try_build`
+    pub fn build(self) -> ApiClient {
+        self.try_build().expect("failed to build the http client")
+    }
+
+    /// build the client, failing rather than connecting directly when the proxy is rejected
+    pub fn try_build(self) -> ApiResult<ApiClient> {
+        let mut client = reqwest::Client::builder()
+            .user_agent(self.user_agent)
+            .timeout(self.timeout)
+            .connect_timeout(self.connect_timeout)
+            .no_proxy();
+        if let Some(proxy) = &self.proxy {
+            let rejected = |err: reqwest::Error| ApiError::proxy(proxy, &err);
+            client = client.proxy(proxy.reqwest_proxy().map_err(rejected)?);
+        }
+        let client = match (client.build(), &self.proxy) {
+            (Ok(client), _) => client,
+            (Err(err), Some(proxy)) => return Err(ApiError::proxy(proxy, &err)),
+            (Err(err), None) => return Err(ApiError::Network(err)),
+        };
+
+        Ok(ApiClient {
+            client,
+            base_url: self.base_url,
+            main_base_url: self.main_base_url,
+            cookie: self.cookie,
+            proxy: self.proxy,
+            proxy_error: self.proxy_error,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_csrf_in_cookie() {
+        let api = ApiClient::new(Some("SESSDATA=abc; bili_jct=def; buvid3=ghi".to_string()));
+        assert_eq!(api.csrf(), Some("def"));
+
+        let api = ApiClient::new(Some("SESSDATA=abc".to_string()));
+        assert_eq!(api.csrf(), None);
+    }
+
+    #[test]
+    fn finds_uid_in_cookie() {
+        let api = ApiClient::new(Some("SESSDATA=abc; DedeUserID=12345".to_string()));
+        assert_eq!(api.uid(), Some(12345));
+
+        let api = ApiClient::new(Some("SESSDATA=abc".to_string()));
+        assert_eq!(api.uid(), None);
+    }
+
+    #[tokio::test]
+    async fn sends_cookie_and_user_agent_to_base_url() {
+        let mut server = mockito::Server::new_async().await;
+        let mock = server
+            .mock("GET", "/ping")
+            .match_header("cookie", "SESSDATA=abc")
+            .match_header("user-agent", "bilicli-test")
+            .create_async()
+            .await;
+
+        let api = ApiClient::builder()
+            .base_url(format!("{}/", server.url()))
+            .cookie(Some("SESSDATA=abc".to_string()))
+            .user_agent("bilicli-test")
+            .build();
+        api.get("/ping").send().await.unwrap();
+
+        mock.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn separates_error_kinds() {
+        let mut server = mockito::Server::new_async().await;
+        server
+            .mock("GET", "/status")
+            .with_status(412)
+            .create_async()
+            .await;
+        server
+            .mock("GET", "/html")
+            .with_body("<html>")
+            .create_async()
+            .await;
+        server
+            .mock("GET", "/business")
+            .with_body(r#"{"code":10030,"message":"您发送弹幕的频率过快","data":[]}"#)
+            .create_async()
+            .await;
+
+        let api = ApiClient::builder().base_url(server.url()).build();
+        let send = |path| api.send::<Value>(api.get(path));
+
+        assert!(matches!(send("/status").await, Err(ApiError::Status(status)) if status == 412));
+        assert!(matches!(send("/html").await, Err(ApiError::Decode(_))));
+        assert!(send("/business").await.unwrap_err().is_too_frequent());
+
+        let api = ApiClient::builder().base_url("http://127.0.0.1:1").build();
+        assert!(matches!(
+            api.send::<Value>(api.get("/")).await,
+            Err(ApiError::Network(_))
+        ));
+
+        let api = ApiClient::builder()
+            .base_url(server.url())
+            .proxy(ProxyConfig::parse("socks5://127.0.0.1:1").ok())
+            .build();
+        let err = api.send::<Value>(api.get("/business")).await.unwrap_err();
+        assert!(
+            matches!(err, ApiError::Proxy { ref proxy, .. } if proxy == "socks5://127.0.0.1:1")
+        );
+
+        let api = ApiClient::builder()
+            .base_url(server.url())
+            .proxy_error(Some("代理地址无效: 缺少协议".to_string()))
+            .build();
+        let err = api.send::<Value>(api.get("/business")).await.unwrap_err();
+        assert!(matches!(err, ApiError::ProxyUnusable(_)));
+    }
+}
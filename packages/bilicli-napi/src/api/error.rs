@@ -0,0 +1,190 @@
+use std::{error::Error, fmt};
+
+use reqwest::StatusCode;
+
+use super::ProxyConfig;
+
+/// Bilibili business codes the TUI can explain
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BusinessError {
+    NotLoggedIn,
+    CsrfFailed,
+    RiskControl,
+    Forbidden,
+    RoomNotFound,
+    Muted,
+    TooFrequent,
+    Unknown,
+}
+
+impl BusinessError {
+    pub fn from_code(code: i32) -> Self {
+        match code {
+            -101 => BusinessError::NotLoggedIn,
+            -111 => BusinessError::CsrfFailed,
+            -352 | -412 => BusinessError::RiskControl,
+            -403 => BusinessError::Forbidden,
+            19002000 | 60004 => BusinessError::RoomNotFound,
+            1003 => BusinessError::Muted,
+            10030 | 10031 => BusinessError::TooFrequent,
+            _ => BusinessError::Unknown,
+        }
+    }
+
+    fn explain(&self) -> Option<&'static str> {
+        match self {
+            BusinessError::NotLoggedIn => Some("未登录或登录已过期, 请更新 Cookie"),
+            BusinessError::CsrfFailed => Some("csrf 校验失败, 请检查 Cookie 中的 bili_jct"),
+            BusinessError::RiskControl => Some("请求被风控拦截, 请稍后再试"),
+            BusinessError::Forbidden => Some("没有权限执行此操作"),
+            BusinessError::RoomNotFound => Some("直播间不存在"),
+            BusinessError::Muted => Some("你在本直播间已被禁言"),
+            BusinessError::TooFrequent => Some("发送频率过快, 请稍后再试"),
+            BusinessError::Unknown => None,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum ApiError {
+    /// the request never got a response, e.g. DNS, connect or timeout failures
+    Network(reqwest::Error),
+    /// the configured proxy could not be reached or refused to connect to the server
+    Proxy { proxy: String, reason: String },
+    /// a proxy was asked for but cannot be used, so nothing is sent rather than going direct
+    ProxyUnusable(String),
+    /// the server answered with a non-success HTTP status
+    Status(StatusCode),
+    /// the response body is not the JSON we expect
+    Decode(String),
+    /// the server answered with a non-zero business `code`
+    Business { code: i32, message: String },
+    /// the input is neither a room id nor a live room url
+    InvalidRoom(String),
+    /// no live room belongs to the given streamer uid or name
+    StreamerNotFound(String),
+}
+
+pub type ApiResult<T> = std::result::Result<T, ApiError>;
+
+impl ApiError {
+    pub fn business(code: i32, message: impl Into<String>) -> Self {
+        ApiError::Business {
+            code,
+            message: message.into(),
+        }
+    }
+
+    /// a connect failure while every request goes through `proxy`
+    pub fn proxy(proxy: &ProxyConfig, err: &reqwest::Error) -> Self {
+        // the innermost error names the actual cause, e.g. a refused connection or a 407
+        let mut source: &dyn Error = err;
+        while let Some(inner) = source.source() {
+            source = inner;
+        }
+
+        ApiError::Proxy {
+            proxy: proxy.to_string(),
+            reason: source.to_string(),
+        }
+    }
+
+    /// the Bilibili business code, if the server returned one
+    pub fn code(&self) -> Option<i32> {
+        match self {
+            ApiError::Business { code, .. } => Some(*code),
+            _ => None,
+        }
+    }
+
+    pub fn kind(&self) -> Option<BusinessError> {
+        self.code().map(BusinessError::from_code)
+    }
+
+    pub fn is_too_frequent(&self) -> bool {
+        self.kind() == Some(BusinessError::TooFrequent)
+    }
+}
+
+impl fmt::Display for ApiError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ApiError::Network(err) if err.is_timeout() => write!(f, "网络请求超时"),
+            ApiError::Network(err) if err.is_connect() => write!(f, "无法连接到服务器"),
+            ApiError::Network(err) => write!(f, "网络错误: {}", err),
+            ApiError::Proxy { proxy, reason } => {
+                write!(f, "无法通过代理 {} 连接到服务器: {}", proxy, reason)
+            }
+            ApiError::ProxyUnusable(reason) => write!(f, "{}, 未发送请求", reason),
+            ApiError::Status(status) => write!(f, "服务器返回错误状态: {}", status),
+            ApiError::Decode(message) => write!(f, "解析响应失败: {}", message),
+            ApiError::Business { code, message } => {
+                match BusinessError::from_code(*code).explain() {
+                    Some(explain) if message.is_empty() => write!(f, "{} ({})", explain, code),
+                    Some(explain) => write!(f, "{}: {} ({})", explain, message, code),
+                    None if message.is_empty() => write!(f, "请求失败 ({})", code),
+                    None => write!(f, "{} ({})", message, code),
+                }
+            }
+            ApiError::InvalidRoom(input) => write!(f, "无法识别的房间号或链接: {}", input),
+            ApiError::StreamerNotFound(streamer) => write!(f, "找不到主播 {} 的直播间", streamer),
+        }
+    }
+}
+
+impl Error for ApiError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            ApiError::Network(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<reqwest::Error> for ApiError {
+    fn from(err: reqwest::Error) -> Self {
+        if err.is_decode() {
+            ApiError::Decode(err.to_string())
+        } else if let Some(status) = err.status() {
+            ApiError::Status(status)
+        } else {
+            ApiError::Network(err)
+        }
+    }
+}
+
+impl From<serde_json::Error> for ApiError {
+    fn from(err: serde_json::Error) -> Self {
+        ApiError::Decode(err.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn explains_known_business_codes() {
+        let err = ApiError::business(10030, "您发送弹幕的频率过快");
+        assert!(err.is_too_frequent());
+        assert_eq!(
+            err.to_string(),
+            "发送频率过快, 请稍后再试: 您发送弹幕的频率过快 (10030)"
+        );
+        assert_eq!(
+            ApiError::business(10030, "").to_string(),
+            "发送频率过快, 请稍后再试 (10030)"
+        );
+
+        let err = ApiError::business(-101, "账号未登录");
+        assert_eq!(err.kind(), Some(BusinessError::NotLoggedIn));
+
+        let err = ApiError::business(1, "分区不存在");
+        assert_eq!(err.kind(), Some(BusinessError::Unknown));
+        assert_eq!(err.to_string(), "分区不存在 (1)");
+
+        let err = ApiError::business(12345, "奇怪的错误");
+        assert_eq!(err.kind(), Some(BusinessError::Unknown));
+        assert_eq!(err.to_string(), "奇怪的错误 (12345)");
+    }
+}
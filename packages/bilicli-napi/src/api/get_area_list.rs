@@ -0,0 +1,74 @@
+use serde::{Deserialize, Deserializer};
+
+use super::{ApiClient, ApiResult};
+
+#[derive(Clone, Deserialize, Debug)]
+pub struct Area {
+    #[serde(deserialize_with = "number_or_string")]
+    pub id: u32,
+    #[serde(deserialize_with = "number_or_string")]
+    pub parent_id: u32,
+    pub name: String,
+}
+
+#[derive(Clone, Deserialize, Debug)]
+pub struct AreaParent {
+    pub id: u32,
+    pub name: String,
+    #[serde(default)]
+    pub list: Vec<Area>,
+}
+
+impl ApiClient {
+    /// every parent area with the sub-areas a live can be categorised in
+    pub async fn get_area_list(&self) -> ApiResult<Vec<AreaParent>> {
+        self.send(self.get("/room/v1/Area/getList")).await
+    }
+}
+
+/// the sub-area ids are sent as strings, the parent ids as numbers
+fn number_or_string<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u32, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Id {
+        Number(u32),
+        String(String),
+    }
+
+    match Id::deserialize(deserializer)? {
+        Id::Number(id) => Ok(id),
+        Id::String(id) => id.parse().map_err(serde::de::Error::custom),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn fetches_areas() {
+        let mut server = mockito::Server::new_async().await;
+        server
+            .mock("GET", "/room/v1/Area/getList")
+            .with_body(
+                r#"{"code":0,"msg":"success","message":"success","data":[
+                    {"id":2,"name":"网游","list":[
+                        {"id":"86","parent_id":"2","old_area_id":"4","name":"英雄联盟"},
+                        {"id":"92","parent_id":"2","old_area_id":"4","name":"DOTA2"}
+                    ]},
+                    {"id":6,"name":"单机游戏","list":[]}
+                ]}"#,
+            )
+            .create_async()
+            .await;
+
+        let api = ApiClient::builder().base_url(server.url()).build();
+        let areas = api.get_area_list().await.unwrap();
+
+        assert_eq!(areas.len(), 2);
+        assert_eq!(areas[0].name, "网游");
+        assert_eq!(areas[0].list[1].id, 92);
+        assert_eq!(areas[0].list[1].parent_id, 2);
+        assert!(areas[1].list.is_empty());
+    }
+}
@@ -0,0 +1,103 @@
+use serde::Deserialize;
+
+use super::{ApiClient, ApiResult};
+
+#[derive(Clone, Deserialize, Debug)]
+pub struct DanmuColor {
+    pub name: String,
+    /// the color as `RRGGBB`
+    pub color_hex: String,
+    /// `1` if the account has unlocked this color
+    pub status: u32,
+}
+
+impl DanmuColor {
+    pub fn value(&self) -> Option<u32> {
+        u32::from_str_radix(self.color_hex.trim_start_matches('#'), 16).ok()
+    }
+}
+
+#[derive(Clone, Deserialize, Debug)]
+pub struct DanmuColorGroup {
+    pub name: String,
+    #[serde(default)]
+    pub color: Vec<DanmuColor>,
+}
+
+#[derive(Clone, Deserialize, Debug)]
+pub struct DanmuModeOption {
+    pub name: String,
+    pub mode: u32,
+    /// `1` if the account may use this mode
+    pub status: u32,
+}
+
+/// the colors and modes offered to the account in a room
+#[derive(Clone, Deserialize, Debug, Default)]
+pub struct DanmuConfig {
+    #[serde(default)]
+    pub group: Vec<DanmuColorGroup>,
+    #[serde(default)]
+    pub mode: Vec<DanmuModeOption>,
+}
+
+impl DanmuConfig {
+    pub fn available_colors(&self) -> Vec<&DanmuColor> {
+        self.group
+            .iter()
+            .flat_map(|group| &group.color)
+            .filter(|color| color.status == 1 && color.value().is_some())
+            .collect()
+    }
+
+    pub fn available_modes(&self) -> Vec<&DanmuModeOption> {
+        self.mode.iter().filter(|mode| mode.status == 1).collect()
+    }
+}
+
+impl ApiClient {
+    /// fetch the danmaku colors and modes the logged in account can use in `room_id`
+    pub async fn get_danmu_config(&self, room_id: u32) -> ApiResult<DanmuConfig> {
+        self.send(self.get(&format!(
+            "/xlive/web-room/v1/dM/GetDMConfigByGroup?room_id={}",
+            room_id
+        )))
+        .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn keeps_only_unlocked_options() {
+        let mut server = mockito::Server::new_async().await;
+        server
+            .mock("GET", "/xlive/web-room/v1/dM/GetDMConfigByGroup?room_id=1")
+            .with_body(
+                r#"{"code":0,"message":"0","data":{"group":[
+                    {"name":"默认","sort":0,"color":[
+                        {"name":"白色","color":"0xffffff","color_hex":"FFFFFF","status":1,"weight":0,"color_id":1,"origin":0},
+                        {"name":"红色","color":"0xff6868","color_hex":"FF6868","status":0,"weight":0,"color_id":2,"origin":0}
+                    ]},
+                    {"name":"粉丝勋章","sort":1,"color":[
+                        {"name":"蓝色","color":"0x5ac4f9","color_hex":"5AC4F9","status":1,"weight":0,"color_id":3,"origin":0}
+                    ]}
+                ],"mode":[
+                    {"name":"滚动","mode":1,"type":"scroll","status":1},
+                    {"name":"顶部","mode":5,"type":"top","status":0}
+                ]}}"#,
+            )
+            .create_async()
+            .await;
+
+        let api = ApiClient::builder().base_url(server.url()).build();
+        let config = api.get_danmu_config(1).await.unwrap();
+
+        let colors = config.available_colors();
+        assert_eq!(colors.len(), 2);
+        assert_eq!(colors[1].value(), Some(0x5AC4F9));
+        assert_eq!(config.available_modes().len(), 1);
+    }
+}
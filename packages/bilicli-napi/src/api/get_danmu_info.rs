@@ -0,0 +1,82 @@
+use serde::Deserialize;
+
+use super::{ApiClient, ApiResult};
+
+#[derive(Clone, Deserialize, Debug)]
+pub struct DanmuHost {
+    pub host: String,
+    pub port: u16,
+    pub wss_port: u16,
+    pub ws_port: u16,
+}
+
+impl DanmuHost {
+    pub fn wss_url(&self) -> String {
+        format!("wss://{}:{}/sub", self.host, self.wss_port)
+    }
+}
+
+#[derive(Clone, Deserialize, Debug)]
+pub struct DanmuInfo {
+    pub token: String,
+    pub host_list: Vec<DanmuHost>,
+}
+
+impl ApiClient {
+    /// fetch the auth token and the candidate danmaku hosts of `room_id`
+    pub async fn get_danmu_info(&self, room_id: u32) -> ApiResult<DanmuInfo> {
+        self.send(self.get(&format!(
+            "/xlive/web-room/v1/index/getDanmuInfo?id={}&type=0",
+            room_id
+        )))
+        .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn fetches_token_and_hosts() {
+        let mut server = mockito::Server::new_async().await;
+        let mock = server
+            .mock("GET", "/xlive/web-room/v1/index/getDanmuInfo?id=21452505&type=0")
+            .match_header("cookie", "SESSDATA=1")
+            .with_body(
+                r#"{"code":0,"message":"0","data":{"token":"abc","host_list":[
+                    {"host":"zj-cn-live-comet.chat.bilibili.com","port":2243,"wss_port":2245,"ws_port":2244},
+                    {"host":"broadcastlv.chat.bilibili.com","port":2243,"wss_port":443,"ws_port":2244}
+                ]}}"#,
+            )
+            .create_async()
+            .await;
+
+        let api = ApiClient::builder()
+            .base_url(server.url())
+            .cookie(Some("SESSDATA=1".to_string()))
+            .build();
+        let info = api.get_danmu_info(21452505).await.unwrap();
+
+        mock.assert_async().await;
+        assert_eq!(info.token, "abc");
+        assert_eq!(
+            info.host_list[0].wss_url(),
+            "wss://zj-cn-live-comet.chat.bilibili.com:2245/sub"
+        );
+    }
+
+    #[tokio::test]
+    async fn reports_business_errors() {
+        let mut server = mockito::Server::new_async().await;
+        server
+            .mock("GET", "/xlive/web-room/v1/index/getDanmuInfo?id=1&type=0")
+            .with_body(r#"{"code":-352,"message":"-352","data":null}"#)
+            .create_async()
+            .await;
+
+        let api = ApiClient::builder().base_url(server.url()).build();
+        let err = api.get_danmu_info(1).await.unwrap_err();
+        assert_eq!(err.code(), Some(-352));
+    }
+}
@@ -0,0 +1,83 @@
+use serde::Deserialize;
+
+use super::{ApiClient, ApiResult};
+
+#[derive(Clone, Deserialize, Debug)]
+pub struct RoomEmoticon {
+    /// the text shown for the emoticon, e.g. `[dog]`
+    pub emoji: String,
+    #[serde(default)]
+    pub descript: String,
+    pub url: String,
+    /// the id sent as message of an emoticon danmaku
+    pub emoticon_unique: String,
+    /// `1` if the account may send this emoticon
+    pub perm: u32,
+    #[serde(default)]
+    pub unlock_show_text: String,
+}
+
+#[derive(Clone, Deserialize, Debug)]
+pub struct EmoticonPackage {
+    pub pkg_id: u32,
+    pub pkg_name: String,
+    pub pkg_type: u32,
+    #[serde(default)]
+    pub emoticons: Vec<RoomEmoticon>,
+}
+
+#[derive(Deserialize)]
+struct EmoticonData {
+    #[serde(default)]
+    data: Vec<EmoticonPackage>,
+}
+
+impl ApiClient {
+    /// fetch the emoticon packages of `room_id`, including the streamer's exclusive ones
+    pub async fn get_emoticons(&self, room_id: u32) -> ApiResult<Vec<EmoticonPackage>> {
+        let data: EmoticonData = self
+            .send(self.get(&format!(
+                "/xlive/web-ucenter/v2/emoticon/GetEmoticons?platform=pc&room_id={}",
+                room_id
+            )))
+            .await?;
+
+        Ok(data.data)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn fetches_packages() {
+        let mut server = mockito::Server::new_async().await;
+        server
+            .mock(
+                "GET",
+                "/xlive/web-ucenter/v2/emoticon/GetEmoticons?platform=pc&room_id=1",
+            )
+            .with_body(
+                r#"{"code":0,"message":"0","data":{"fans_brand":1,"data":[
+                    {"pkg_id":100,"pkg_name":"通用表情","pkg_type":1,"emoticons":[
+                        {"emoji":"[dog]","descript":"","url":"https://i0.hdslb.com/dog.png",
+                         "emoticon_unique":"official_109","perm":1,"unlock_show_text":""}
+                    ]},
+                    {"pkg_id":200,"pkg_name":"房间专属表情","pkg_type":2,"emoticons":[
+                        {"emoji":"赞","descript":"赞","url":"https://i0.hdslb.com/zan.png",
+                         "emoticon_unique":"room_1_1234","perm":0,"unlock_show_text":"粉丝勋章 5 级解锁"}
+                    ]}
+                ]}}"#,
+            )
+            .create_async()
+            .await;
+
+        let api = ApiClient::builder().base_url(server.url()).build();
+        let packages = api.get_emoticons(1).await.unwrap();
+
+        assert_eq!(packages.len(), 2);
+        assert_eq!(packages[0].emoticons[0].emoticon_unique, "official_109");
+        assert_eq!(packages[1].emoticons[0].perm, 0);
+    }
+}
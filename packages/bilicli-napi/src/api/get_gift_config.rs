@@ -0,0 +1,66 @@
+use serde::{Deserialize, Serialize};
+
+use super::{ApiClient, ApiResult};
+
+#[derive(Clone, Deserialize, Serialize, Debug)]
+pub struct GiftConfig {
+    pub id: u32,
+    pub name: String,
+    /// in gold (1000 = 1 元) or silver melon seeds, depending on `coin_type`
+    pub price: u32,
+    /// `gold` or `silver`
+    pub coin_type: String,
+    #[serde(default)]
+    pub img_basic: String,
+}
+
+#[derive(Deserialize)]
+struct GiftConfigData {
+    #[serde(default)]
+    list: Vec<GiftConfig>,
+}
+
+impl ApiClient {
+    /// fetch every gift that can be sent in `room_id`
+    pub async fn get_gift_config(&self, room_id: u32) -> ApiResult<Vec<GiftConfig>> {
+        let data: GiftConfigData = self
+            .send(self.get(&format!(
+                "/xlive/web-room/v1/giftPanel/giftConfig?platform=pc&room_id={}",
+                room_id
+            )))
+            .await?;
+
+        Ok(data.list)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn fetches_gifts() {
+        let mut server = mockito::Server::new_async().await;
+        server
+            .mock(
+                "GET",
+                "/xlive/web-room/v1/giftPanel/giftConfig?platform=pc&room_id=1",
+            )
+            .with_body(
+                r#"{"code":0,"message":"0","data":{"list":[
+                    {"id":1,"name":"辣条","price":100,"coin_type":"silver","img_basic":"https://i0.hdslb.com/1.png"},
+                    {"id":31036,"name":"小花花","price":100,"coin_type":"gold"}
+                ]}}"#,
+            )
+            .create_async()
+            .await;
+
+        let api = ApiClient::builder().base_url(server.url()).build();
+        let gifts = api.get_gift_config(1).await.unwrap();
+
+        assert_eq!(gifts.len(), 2);
+        assert_eq!(gifts[0].coin_type, "silver");
+        assert_eq!(gifts[1].name, "小花花");
+        assert!(gifts[1].img_basic.is_empty());
+    }
+}
@@ -0,0 +1,135 @@
+use serde::Deserialize;
+
+use super::{ApiClient, ApiResult};
+
+/// the guard list is served in pages of this size
+const PAGE_SIZE: u32 = 29;
+
+/// stop paging after this many pages even if the server reports more
+const MAX_PAGES: u32 = 100;
+
+#[derive(Clone, Deserialize, Debug, Default)]
+pub struct GuardMedal {
+    #[serde(default)]
+    pub medal_name: String,
+    #[serde(default)]
+    pub medal_level: u8,
+    #[serde(default)]
+    pub medal_color_start: u32,
+}
+
+/// a governor, admiral or captain of the room
+#[derive(Clone, Deserialize, Debug)]
+pub struct GuardMember {
+    pub uid: u64,
+    pub username: String,
+    pub rank: u32,
+    /// `1` governor, `2` admiral, `3` captain
+    pub guard_level: u8,
+    /// `1` while the member is in the room
+    #[serde(default)]
+    pub is_alive: u8,
+    #[serde(default)]
+    pub medal_info: GuardMedal,
+}
+
+impl GuardMember {
+    pub fn is_online(&self) -> bool {
+        self.is_alive == 1
+    }
+}
+
+#[derive(Clone, Deserialize, Debug, Default)]
+pub struct GuardPageInfo {
+    /// the total number of guards
+    #[serde(default)]
+    pub num: u32,
+    /// the number of pages
+    #[serde(default)]
+    pub page: u32,
+    #[serde(default)]
+    pub now: u32,
+}
+
+#[derive(Clone, Deserialize, Debug, Default)]
+pub struct GuardPage {
+    #[serde(default)]
+    pub info: GuardPageInfo,
+    #[serde(default)]
+    pub list: Vec<GuardMember>,
+    /// the three highest ranked guards, only sent with the first page
+    #[serde(default)]
+    pub top3: Vec<GuardMember>,
+}
+
+impl ApiClient {
+    /// one page of the guards of `room_id` of the streamer `ruid`, starting at page `1`
+    pub async fn get_guard_page(&self, room_id: u32, ruid: u64, page: u32) -> ApiResult<GuardPage> {
+        self.send(self.get(&format!(
+            "/xlive/app-room/v2/guardTab/topList?roomid={}&ruid={}&page={}&page_size={}",
+            room_id, ruid, page, PAGE_SIZE
+        )))
+        .await
+    }
+
+    /// every guard of `room_id`, ordered by rank
+    pub async fn get_guards(&self, room_id: u32, ruid: u64) -> ApiResult<Vec<GuardMember>> {
+        let first = self.get_guard_page(room_id, ruid, 1).await?;
+        let pages = first.info.page.min(MAX_PAGES);
+
+        let mut guards = first.top3;
+        guards.extend(first.list);
+        for page in 2..=pages {
+            guards.extend(self.get_guard_page(room_id, ruid, page).await?.list);
+        }
+
+        Ok(guards)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn fetches_every_page() {
+        let mut server = mockito::Server::new_async().await;
+        let first = server
+            .mock(
+                "GET",
+                "/xlive/app-room/v2/guardTab/topList?roomid=1&ruid=2&page=1&page_size=29",
+            )
+            .with_body(
+                r#"{"code":0,"message":"0","data":{"info":{"num":3,"page":2,"now":1},
+                    "top3":[{"uid":1,"username":"a","rank":1,"guard_level":1,"is_alive":1,
+                             "medal_info":{"medal_name":"粉丝","medal_level":30,"medal_color_start":1725515}}],
+                    "list":[{"uid":2,"username":"b","rank":2,"guard_level":3,"is_alive":0}]}}"#,
+            )
+            .create_async()
+            .await;
+        let second = server
+            .mock(
+                "GET",
+                "/xlive/app-room/v2/guardTab/topList?roomid=1&ruid=2&page=2&page_size=29",
+            )
+            .with_body(
+                r#"{"code":0,"message":"0","data":{"info":{"num":3,"page":2,"now":2},"top3":[],
+                    "list":[{"uid":3,"username":"c","rank":3,"guard_level":2,"is_alive":1}]}}"#,
+            )
+            .create_async()
+            .await;
+
+        let api = ApiClient::builder().base_url(server.url()).build();
+        let guards = api.get_guards(1, 2).await.unwrap();
+
+        first.assert_async().await;
+        second.assert_async().await;
+        assert_eq!(
+            guards.iter().map(|guard| guard.uid).collect::<Vec<_>>(),
+            [1, 2, 3]
+        );
+        assert!(guards[0].is_online());
+        assert_eq!(guards[0].medal_info.medal_level, 30);
+        assert!(!guards[1].is_online());
+    }
+}
@@ -0,0 +1,96 @@
+use serde::Deserialize;
+use serde_json::Value;
+
+use super::{ApiClient, ApiResult};
+
+#[derive(Clone, Deserialize, Debug, Default)]
+pub struct HistoryCheckInfo {
+    /// unix timestamp in seconds
+    #[serde(default)]
+    pub ts: i64,
+}
+
+#[derive(Clone, Deserialize, Debug, Default)]
+pub struct HistoryEmoticon {
+    #[serde(default)]
+    pub emoticon_unique: String,
+    #[serde(default)]
+    pub url: String,
+    #[serde(default)]
+    pub width: i32,
+    #[serde(default)]
+    pub height: i32,
+}
+
+/// a danmaku sent before we joined the room
+#[derive(Clone, Deserialize, Debug)]
+pub struct HistoryDanmu {
+    pub text: String,
+    pub uid: u64,
+    pub nickname: String,
+    /// the local send time, e.g. `2024-01-01 12:00:00`
+    pub timeline: String,
+    #[serde(default)]
+    pub check_info: HistoryCheckInfo,
+    /// the fan medal in the same array layout as the `DANMU_MSG` info
+    #[serde(default)]
+    pub medal: Vec<Value>,
+    #[serde(default)]
+    pub guard_level: u8,
+    #[serde(default)]
+    pub isadmin: u8,
+    #[serde(default)]
+    pub emoticon: HistoryEmoticon,
+}
+
+#[derive(Deserialize)]
+struct HistoryData {
+    #[serde(default)]
+    room: Vec<HistoryDanmu>,
+}
+
+impl ApiClient {
+    /// the latest danmaku of `room_id`, oldest first
+    pub async fn get_history(&self, room_id: u32) -> ApiResult<Vec<HistoryDanmu>> {
+        let data: HistoryData = self
+            .send(self.get(&format!(
+                "/xlive/web-room/v1/dM/gethistory?roomid={}&room_type=0",
+                room_id
+            )))
+            .await?;
+
+        Ok(data.room)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn fetches_room_history() {
+        let mut server = mockito::Server::new_async().await;
+        server
+            .mock("GET", "/xlive/web-room/v1/dM/gethistory?roomid=1&room_type=0")
+            .with_body(
+                r#"{"code":0,"message":"0","data":{"admin":[],"room":[
+                    {"text":"晚上好","uid":1,"nickname":"a","timeline":"2024-01-01 12:00:00",
+                     "isadmin":0,"guard_level":3,"medal":[],"check_info":{"ts":1704081600,"ct":"x"}},
+                    {"text":"[dog]","uid":2,"nickname":"b","timeline":"2024-01-01 12:00:05","isadmin":1,
+                     "medal":[21,"粉丝","主播",1,6067854,"",0,6067854,6067854,6067854,0,{},2],
+                     "emoticon":{"emoticon_unique":"emoji_1","url":"https://i0.hdslb.com/dog.png","width":60,"height":60}}
+                ]}}"#,
+            )
+            .create_async()
+            .await;
+
+        let api = ApiClient::builder().base_url(server.url()).build();
+        let history = api.get_history(1).await.unwrap();
+
+        assert_eq!(history.len(), 2);
+        assert_eq!(history[0].check_info.ts, 1704081600);
+        assert_eq!(history[0].guard_level, 3);
+        assert_eq!(history[1].medal.len(), 13);
+        assert_eq!(history[1].emoticon.emoticon_unique, "emoji_1");
+    }
+}
@@ -1,5 +1,7 @@
 use serde::Deserialize;
 
+use super::{ApiClient, ApiResult};
+
 #[derive(Clone, Deserialize, Debug)]
 pub struct RoomInfo {
     pub uid: u64,
@@ -21,28 +23,9 @@ pub struct RoomInfo {
     pub area_name: String,
 }
 
-#[derive(Deserialize)]
-pub struct GetRoomInfoResponse {
-    pub code: i32,
-    pub message: String,
-    pub data: RoomInfo,
-}
-
-pub async fn get_room_info(room_id: u32) -> Result<RoomInfo, Box<dyn std::error::Error>> {
-    let response = reqwest::get(format!(
-        "https://api.live.bilibili.com/room/v1/Room/get_info?room_id={}",
-        room_id
-    ))
-    .await?;
-
-    let response = response.json::<GetRoomInfoResponse>().await?;
-
-    if response.code != 0 {
-        Err(Box::new(std::io::Error::new(
-            std::io::ErrorKind::NotFound,
-            response.message,
-        )))?
+impl ApiClient {
+    pub async fn get_room_info(&self, room_id: u32) -> ApiResult<RoomInfo> {
+        self.send(self.get(&format!("/room/v1/Room/get_info?room_id={}", room_id)))
+            .await
     }
-
-    Ok(response.data)
 }
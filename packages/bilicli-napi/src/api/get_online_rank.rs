@@ -0,0 +1,93 @@
+use serde::Deserialize;
+
+use super::{ApiClient, ApiResult};
+
+#[derive(Clone, Deserialize, Debug, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct RankMedal {
+    #[serde(default)]
+    pub medal_name: String,
+    #[serde(default)]
+    pub level: u8,
+    #[serde(default)]
+    pub medal_color_start: u32,
+    #[serde(default)]
+    pub medal_color_end: u32,
+    /// the uid of the streamer the medal belongs to
+    #[serde(default)]
+    pub target_id: u64,
+}
+
+/// a viewer of the online gold rank, i.e. the high-energy list
+#[derive(Clone, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct OnlineRankUser {
+    pub user_rank: u32,
+    pub uid: u64,
+    pub name: String,
+    #[serde(default)]
+    pub face: String,
+    /// the gold contributed during the current live
+    pub score: u64,
+    #[serde(default)]
+    pub medal_info: Option<RankMedal>,
+    #[serde(default, rename = "guard_level")]
+    pub guard_level: u8,
+}
+
+#[derive(Clone, Deserialize, Debug, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct OnlineRank {
+    /// how many viewers are ranked, not only the ones returned
+    #[serde(default)]
+    pub online_num: u32,
+    #[serde(default, rename = "OnlineRankItem")]
+    pub users: Vec<OnlineRankUser>,
+}
+
+impl ApiClient {
+    /// the top contributors currently in `room_id` of the streamer `ruid`
+    pub async fn get_online_rank(&self, ruid: u64, room_id: u32) -> ApiResult<OnlineRank> {
+        self.send(self.get(&format!(
+            "/xlive/general-interface/v1/rank/getOnlineGoldRank?ruid={}&roomId={}&page=1&pageSize=50",
+            ruid, room_id
+        )))
+        .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn fetches_ranked_viewers() {
+        let mut server = mockito::Server::new_async().await;
+        server
+            .mock(
+                "GET",
+                "/xlive/general-interface/v1/rank/getOnlineGoldRank?ruid=2&roomId=1&page=1&pageSize=50",
+            )
+            .with_body(
+                r#"{"code":0,"message":"0","data":{"onlineNum":120,"OnlineRankItem":[
+                    {"userRank":1,"uid":10001,"name":"viewer","face":"","score":5200,"guard_level":3,
+                     "medalInfo":{"guardLevel":3,"medalColorStart":1725515,"medalColorEnd":5414290,
+                                  "medalColorBorder":6809855,"medalName":"粉丝","level":21,"targetId":2}},
+                    {"userRank":2,"uid":10002,"name":"other","score":100,"medalInfo":null}
+                ],"ownInfo":{}}}"#,
+            )
+            .create_async()
+            .await;
+
+        let api = ApiClient::builder().base_url(server.url()).build();
+        let rank = api.get_online_rank(2, 1).await.unwrap();
+
+        assert_eq!(rank.online_num, 120);
+        assert_eq!(rank.users[0].guard_level, 3);
+        assert_eq!(
+            rank.users[0].medal_info.as_ref().unwrap().medal_name,
+            "粉丝"
+        );
+        assert!(rank.users[1].medal_info.is_none());
+    }
+}
@@ -0,0 +1,66 @@
+use serde::Deserialize;
+
+use super::{ApiClient, ApiError, ApiResult};
+
+#[derive(Clone, Deserialize, Debug)]
+pub struct StreamerRoom {
+    #[serde(rename = "roomStatus")]
+    pub room_status: u32,
+    #[serde(rename = "liveStatus")]
+    pub live_status: u32,
+    #[serde(rename = "roomid")]
+    pub room_id: u32,
+    pub title: String,
+    pub url: String,
+}
+
+impl ApiClient {
+    /// find the live room of the user `uid`
+    pub async fn get_room_by_uid(&self, uid: u64) -> ApiResult<StreamerRoom> {
+        let room: StreamerRoom = self
+            .send(self.get(&format!("/room/v1/Room/getRoomInfoOld?mid={}", uid)))
+            .await?;
+
+        // users without a live room get an empty placeholder instead of an error
+        if room.room_status == 0 || room.room_id == 0 {
+            return Err(ApiError::StreamerNotFound(format!("UID {}", uid)));
+        }
+
+        Ok(room)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn finds_room_of_uid() {
+        let mut server = mockito::Server::new_async().await;
+        server
+            .mock("GET", "/room/v1/Room/getRoomInfoOld?mid=11153765")
+            .with_body(
+                r#"{"code":0,"message":"0","data":{"roomStatus":1,"roundStatus":0,
+                    "liveStatus":1,"url":"https://live.bilibili.com/510","title":"直播",
+                    "cover":"","online":1,"roomid":80397,"broadcast_type":0,"online_hidden":0}}"#,
+            )
+            .create_async()
+            .await;
+        server
+            .mock("GET", "/room/v1/Room/getRoomInfoOld?mid=1")
+            .with_body(
+                r#"{"code":0,"message":"0","data":{"roomStatus":0,"roundStatus":0,
+                    "liveStatus":0,"url":"","title":"","cover":"","online":0,"roomid":0,
+                    "broadcast_type":0,"online_hidden":0}}"#,
+            )
+            .create_async()
+            .await;
+
+        let api = ApiClient::builder().base_url(server.url()).build();
+        assert_eq!(api.get_room_by_uid(11153765).await.unwrap().room_id, 80397);
+        assert!(matches!(
+            api.get_room_by_uid(1).await,
+            Err(ApiError::StreamerNotFound(_))
+        ));
+    }
+}
@@ -1,12 +1,18 @@
 use serde::Deserialize;
 
+use super::{ApiClient, ApiResult, DanmuMode, SendDanmuOptions};
+
 #[derive(Deserialize, Default, Debug)]
 pub struct WearedV2 {
     pub(crate) name: String,
     pub(crate) level: u32,
     pub(crate) guard_level: u32,
     pub(crate) v2_medal_color_text: String,
-    pub(crate) v2_medal_color_level: String,
+    #[serde(default)]
+    pub(crate) v2_medal_color_start: String,
+    /// the uid of the streamer the medal belongs to
+    #[serde(default)]
+    pub(crate) target_id: u64,
 }
 
 #[derive(Deserialize)]
@@ -14,26 +20,57 @@ pub struct Medal {
     pub(crate) curr_weared_v2: WearedV2,
 }
 
+/// the danmaku defaults of the account in this room
+#[derive(Deserialize, Debug)]
+#[serde(default)]
+pub struct DanmuProperty {
+    pub(crate) mode: u32,
+    pub(crate) color: u32,
+    /// the longest danmaku the account may send
+    pub(crate) length: u32,
+}
+
+impl Default for DanmuProperty {
+    fn default() -> Self {
+        Self {
+            mode: 1,
+            color: 0xFFFFFF,
+            length: 20,
+        }
+    }
+}
+
+#[derive(Deserialize, Default, Debug)]
+pub struct UserProperty {
+    #[serde(default)]
+    pub(crate) danmu: DanmuProperty,
+}
+
 #[derive(Deserialize)]
 pub struct RoomUserInfo {
     pub medal: Medal,
+    #[serde(default)]
+    pub property: UserProperty,
 }
 
-#[derive(Deserialize)]
-struct GetRoomByUserResponse {
-    code: u32,
-    message: String,
-    data: RoomUserInfo,
-}
-
-pub async fn get_room_by_user(room_id: u32) -> Result<RoomUserInfo, reqwest::Error> {
-    let resp = reqwest::get(format!(
-        "https://api.live.bilibili.com/xlive/web-room/v1/index/getInfoByUser?room_id={}",
-        room_id
-    ))
-    .await?
-    .json::<GetRoomByUserResponse>()
-    .await?;
-
-    Ok(resp.data)
+impl RoomUserInfo {
+    /// the options the account last sent danmaku with
+    pub fn send_options(&self) -> SendDanmuOptions {
+        let danmu = &self.property.danmu;
+        SendDanmuOptions {
+            color: danmu.color,
+            mode: DanmuMode::from_code(danmu.mode).unwrap_or_default(),
+            ..Default::default()
+        }
+    }
+}
+
+impl ApiClient {
+    pub async fn get_room_by_user(&self, room_id: u32) -> ApiResult<RoomUserInfo> {
+        self.send(self.get(&format!(
+            "/xlive/web-room/v1/index/getInfoByUser?room_id={}",
+            room_id
+        )))
+        .await
+    }
 }
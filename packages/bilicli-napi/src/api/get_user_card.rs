@@ -0,0 +1,69 @@
+use serde::Deserialize;
+
+use super::{ApiClient, ApiResult};
+
+#[derive(Clone, Deserialize, Debug, Default)]
+pub struct LevelInfo {
+    #[serde(default)]
+    pub current_level: u8,
+}
+
+#[derive(Clone, Deserialize, Debug)]
+pub struct CardInfo {
+    pub name: String,
+    #[serde(default)]
+    pub face: String,
+    #[serde(default)]
+    pub sex: String,
+    #[serde(default)]
+    pub sign: String,
+    #[serde(default)]
+    pub level_info: LevelInfo,
+}
+
+/// the profile of an account as shown in its hover card on the main site
+#[derive(Clone, Deserialize, Debug)]
+pub struct UserCard {
+    pub card: CardInfo,
+    #[serde(default)]
+    pub follower: u32,
+    #[serde(default)]
+    pub archive_count: u32,
+    #[serde(default)]
+    pub like_num: u32,
+}
+
+impl ApiClient {
+    pub async fn get_user_card(&self, uid: u64) -> ApiResult<UserCard> {
+        self.send(self.get_main(&format!("/x/web-interface/card?mid={}&photo=false", uid)))
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn fetches_card_from_main_api() {
+        let mut server = mockito::Server::new_async().await;
+        server
+            .mock("GET", "/x/web-interface/card?mid=2&photo=false")
+            .with_body(
+                r#"{"code":0,"message":"0","ttl":1,"data":{
+                    "card":{"mid":"2","name":"碧诗","sex":"男","face":"https://i0.hdslb.com/face.jpg",
+                            "sign":"kami.im","level_info":{"current_level":6}},
+                    "following":false,"archive_count":50,"follower":1000000,"like_num":200
+                }}"#,
+            )
+            .create_async()
+            .await;
+
+        let api = ApiClient::builder().main_base_url(server.url()).build();
+        let card = api.get_user_card(2).await.unwrap();
+
+        assert_eq!(card.card.name, "碧诗");
+        assert_eq!(card.card.level_info.current_level, 6);
+        assert_eq!(card.follower, 1000000);
+    }
+}
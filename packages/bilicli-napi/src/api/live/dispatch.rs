@@ -0,0 +1,464 @@
+use chrono::{Local, NaiveDateTime};
+use serde_json::Value;
+
+use crate::{
+    api::HistoryDanmu,
+    ui::{
+        Anchor, Badge, Combo, DanmuMsg, Emoticon, GiftMsg, GuardBuyMsg, Identity, Master,
+        SuperChatMsg, User, UserActionMsg,
+    },
+};
+
+/// the `ROOM_CHANGE` payload, i.e. the header fields of `RoomInfo` that can change mid-stream
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RoomChange {
+    pub title: String,
+    pub area_name: String,
+    pub parent_area_name: String,
+}
+
+/// a raw live command mapped to what the TUI consumes
+pub enum LiveCommand {
+    Danmu(DanmuMsg),
+    SuperChat(SuperChatMsg),
+    Gift(GiftMsg),
+    GuardBuy(GuardBuyMsg),
+    UserAction(UserActionMsg),
+    Watcher(u32),
+    Attention(u32),
+    Live(bool),
+    RoomChange(RoomChange),
+    /// how many viewers are on the online gold rank
+    OnlineRankCount(u32),
+    /// the top of the online gold rank changed
+    OnlineRankChanged,
+    /// a command we have no mapping for, or one whose payload could not be parsed
+    Unknown(String),
+}
+
+/// map a raw command received in room `room_id` to a typed `LiveCommand`
+pub fn dispatch(command: &Value, room_id: u32) -> LiveCommand {
+    let cmd = command["cmd"].as_str().unwrap_or_default();
+    let data = &command["data"];
+
+    // `DANMU_MSG` may carry a suffix such as `DANMU_MSG:4:0:2:2:2:0`
+    let parsed = match cmd.split(':').next().unwrap_or_default() {
+        "DANMU_MSG" => parse_danmu(command, room_id).map(LiveCommand::Danmu),
+        "SUPER_CHAT_MESSAGE" => parse_super_chat(data, room_id).map(LiveCommand::SuperChat),
+        "SEND_GIFT" => parse_gift(data, room_id).map(LiveCommand::Gift),
+        "GUARD_BUY" => parse_guard_buy(data).map(LiveCommand::GuardBuy),
+        "INTERACT_WORD" => {
+            let action = match data["msg_type"].as_u64() {
+                Some(1) => "enter",
+                Some(2 | 4 | 5) => "follow",
+                Some(3) => "share",
+                _ => "",
+            };
+            parse_user_action(data, action, room_id).map(LiveCommand::UserAction)
+        }
+        "LIKE_INFO_V3_CLICK" => {
+            parse_user_action(data, "like", room_id).map(LiveCommand::UserAction)
+        }
+        "WATCHED_CHANGE" => data["num"]
+            .as_u64()
+            .map(|num| LiveCommand::Watcher(num as u32)),
+        "ROOM_REAL_TIME_MESSAGE_UPDATE" => data["fans"]
+            .as_u64()
+            .map(|fans| LiveCommand::Attention(fans as u32)),
+        "ONLINE_RANK_COUNT" => data["count"]
+            .as_u64()
+            .map(|count| LiveCommand::OnlineRankCount(count as u32)),
+        "ONLINE_RANK_V2" | "ONLINE_RANK_TOP3" => Some(LiveCommand::OnlineRankChanged),
+        "LIVE" => Some(LiveCommand::Live(true)),
+        "PREPARING" => Some(LiveCommand::Live(false)),
+        "ROOM_CHANGE" => Some(LiveCommand::RoomChange(RoomChange {
+            title: string(&data["title"]),
+            area_name: string(&data["area_name"]),
+            parent_area_name: string(&data["parent_area_name"]),
+        })),
+        _ => None,
+    };
+
+    parsed.unwrap_or_else(|| LiveCommand::Unknown(cmd.to_string()))
+}
+
+fn parse_danmu(command: &Value, room_id: u32) -> Option<DanmuMsg> {
+    let info = command.get("info")?;
+    let meta = info.get(0)?;
+    let sender = info.get(2)?;
+
+    let badge = info
+        .get(3)
+        .and_then(Value::as_array)
+        .and_then(|medal| parse_medal_array(medal, room_id));
+
+    let user = User {
+        uid: sender.get(0)?.as_u64()?,
+        uname: sender.get(1)?.as_str()?.to_string(),
+        face: meta
+            .pointer("/15/user/base/face")
+            .and_then(Value::as_str)
+            .map(str::to_string),
+        badge,
+        identity: Some(Identity {
+            rank: info.pointer("/4/4").map(number).unwrap_or_default() as u8,
+            guard_level: info.get(7).map(number).unwrap_or_default() as u8,
+            room_admin: sender.get(2).map(number) == Some(1),
+        }),
+    };
+
+    let emoticon = meta.get(13).and_then(|emoticon| {
+        Some(Emoticon {
+            id: emoticon.get("emoticon_unique")?.as_str()?.to_string(),
+            height: emoticon.get("height")?.as_i64()? as i32,
+            width: emoticon.get("width")?.as_i64()? as i32,
+            url: emoticon.get("url")?.as_str()?.to_string(),
+        })
+    });
+
+    Some(DanmuMsg::new(
+        user,
+        info.get(1)?.as_str()?.to_string(),
+        meta.get(4)?.as_i64()?,
+        meta.get(9).map(number).unwrap_or_default() != 0,
+        emoticon,
+    ))
+}
+
+/// map a danmaku of the room history, marked as history
+pub fn history_danmu(danmu: HistoryDanmu, room_id: u32) -> DanmuMsg {
+    let timestamp = match danmu.check_info.ts {
+        0 => NaiveDateTime::parse_from_str(&danmu.timeline, "%Y-%m-%d %H:%M:%S")
+            .ok()
+            .and_then(|time| time.and_local_timezone(Local).single())
+            .unwrap_or_else(Local::now)
+            .timestamp_millis(),
+        seconds => seconds * 1000,
+    };
+
+    let user = User {
+        uid: danmu.uid,
+        uname: danmu.nickname,
+        face: None,
+        badge: parse_medal_array(&danmu.medal, room_id),
+        identity: Some(Identity {
+            rank: 0,
+            guard_level: danmu.guard_level,
+            room_admin: danmu.isadmin == 1,
+        }),
+    };
+
+    let emoticon = Some(danmu.emoticon)
+        .filter(|emoticon| !emoticon.emoticon_unique.is_empty())
+        .map(|emoticon| Emoticon {
+            id: emoticon.emoticon_unique,
+            height: emoticon.height,
+            width: emoticon.width,
+            url: emoticon.url,
+        });
+
+    DanmuMsg::new(user, danmu.text, timestamp, false, emoticon).into_history()
+}
+
+fn parse_super_chat(data: &Value, room_id: u32) -> Option<SuperChatMsg> {
+    let user_info = &data["user_info"];
+
+    Some(SuperChatMsg {
+        id: data["id"].as_u64()?,
+        user: User {
+            uid: data["uid"].as_u64()?,
+            uname: user_info["uname"].as_str()?.to_string(),
+            face: user_info["face"].as_str().map(str::to_string),
+            badge: parse_medal_info(&data["medal_info"], room_id),
+            identity: Some(Identity {
+                rank: 0,
+                guard_level: number(&user_info["guard_level"]) as u8,
+                room_admin: number(&user_info["manager"]) == 1,
+            }),
+        },
+        content: data["message"].as_str()?.to_string(),
+        content_color: string(&data["message_font_color"]),
+        price: number(&data["price"]) as u32,
+        time: number(&data["time"]) as u32,
+        timestamp: seconds_to_millis(&data["start_time"]),
+    })
+}
+
+fn parse_gift(data: &Value, room_id: u32) -> Option<GiftMsg> {
+    let send_master = data["send_master"].as_object().and_then(|master| {
+        Some(Master {
+            uid: master.get("uid")?.as_u64()?,
+            uname: master.get("uname")?.as_str()?.to_string(),
+            room_id: master.get("room_id").map(number).unwrap_or_default() as u32,
+        })
+    });
+
+    let combo = data["batch_combo_id"]
+        .as_str()
+        .filter(|batch_id| !batch_id.is_empty())
+        .map(|batch_id| Combo {
+            batch_id: batch_id.to_string(),
+            combo_num: number(&data["super_batch_gift_num"]) as u32,
+            total_price: number(&data["combo_total_coin"]) as u32,
+        });
+
+    Some(GiftMsg {
+        timestamp: seconds_to_millis(&data["timestamp"]),
+        user: User {
+            uid: data["uid"].as_u64()?,
+            uname: data["uname"].as_str()?.to_string(),
+            face: data["face"].as_str().map(str::to_string),
+            badge: parse_medal_info(&data["medal_info"], room_id),
+            identity: Some(Identity {
+                rank: 0,
+                guard_level: number(&data["guard_level"]) as u8,
+                room_admin: false,
+            }),
+        },
+        gift_id: data["giftId"].as_u64()? as u32,
+        gift_name: data["giftName"].as_str()?.to_string(),
+        coin_type: string(&data["coin_type"]),
+        price: number(&data["price"]) as u32,
+        amount: number(&data["num"]) as u32,
+        send_master,
+        combo,
+    })
+}
+
+fn parse_guard_buy(data: &Value) -> Option<GuardBuyMsg> {
+    let guard_level = number(&data["guard_level"]) as u8;
+
+    Some(GuardBuyMsg {
+        timestamp: seconds_to_millis(&data["start_time"]),
+        user: User {
+            uid: data["uid"].as_u64()?,
+            uname: data["username"].as_str()?.to_string(),
+            face: None,
+            badge: None,
+            identity: Some(Identity {
+                rank: 0,
+                guard_level,
+                room_admin: false,
+            }),
+        },
+        gift_id: number(&data["gift_id"]) as u32,
+        gift_name: data["gift_name"].as_str()?.to_string(),
+        guard_level,
+        price: number(&data["price"]) as u32,
+        start_time: number(&data["start_time"]) as u32,
+        end_time: number(&data["end_time"]) as u32,
+    })
+}
+
+fn parse_user_action(data: &Value, action: &str, room_id: u32) -> Option<UserActionMsg> {
+    if action.is_empty() {
+        return None;
+    }
+
+    Some(UserActionMsg {
+        user: User {
+            uid: data["uid"].as_u64()?,
+            uname: data["uname"].as_str()?.to_string(),
+            face: data
+                .pointer("/uinfo/base/face")
+                .and_then(Value::as_str)
+                .map(str::to_string),
+            badge: parse_medal_info(&data["fans_medal"], room_id),
+            identity: None,
+        },
+        action: action.to_string(),
+        timestamp: seconds_to_millis(&data["timestamp"]),
+    })
+}
+
+/// a fan medal in the array layout of `DANMU_MSG`, shared by the danmaku history
+fn parse_medal_array(medal: &[Value], room_id: u32) -> Option<Badge> {
+    if medal.len() <= 12 {
+        return None;
+    }
+
+    let anchor_room_id = number(&medal[3]) as u32;
+    Some(Badge::new(
+        string(&medal[1]),
+        number(&medal[0]) as u8,
+        int_to_color(&medal[4]),
+        Some(vec![int_to_color(&medal[8]), int_to_color(&medal[9])]),
+        Some(Anchor {
+            uid: number(&medal[12]),
+            uname: string(&medal[2]),
+            room_id: anchor_room_id,
+            is_same_room: Some(anchor_room_id == room_id),
+        }),
+        None,
+    ))
+}
+
+/// parse the `medal_info` / `fans_medal` object shared by most commands
+fn parse_medal_info(medal: &Value, room_id: u32) -> Option<Badge> {
+    let level = number(&medal["medal_level"]);
+    let name = medal["medal_name"]
+        .as_str()
+        .filter(|name| !name.is_empty())?;
+    if level == 0 {
+        return None;
+    }
+
+    let anchor_room_id = number(&medal["anchor_roomid"]) as u32;
+    Some(Badge::new(
+        name.to_string(),
+        level as u8,
+        int_to_color(&medal["medal_color"]),
+        Some(vec![
+            int_to_color(&medal["medal_color_start"]),
+            int_to_color(&medal["medal_color_end"]),
+        ]),
+        Some(Anchor {
+            uid: number(&medal["target_id"]),
+            uname: string(&medal["anchor_uname"]),
+            room_id: anchor_room_id,
+            is_same_room: Some(anchor_room_id == room_id),
+        }),
+        None,
+    ))
+}
+
+fn number(value: &Value) -> u64 {
+    value
+        .as_u64()
+        .or_else(|| value.as_str().and_then(|value| value.parse().ok()))
+        .unwrap_or_default()
+}
+
+fn string(value: &Value) -> String {
+    value.as_str().unwrap_or_default().to_string()
+}
+
+/// convert a second timestamp to milliseconds, falling back to now
+fn seconds_to_millis(value: &Value) -> i64 {
+    match number(value) {
+        0 => Local::now().timestamp_millis(),
+        seconds => seconds as i64 * 1000,
+    }
+}
+
+/// convert a color number such as `6809855` to `#67E8FF`
+fn int_to_color(value: &Value) -> String {
+    format!("#{:06X}", number(value))
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn maps_gift_with_medal() {
+        let command = json!({
+            "cmd": "SEND_GIFT",
+            "data": {
+                "uid": 10001, "uname": "viewer", "face": "https://i0.hdslb.com/face.jpg",
+                "giftId": 31036, "giftName": "小花花", "coin_type": "gold", "price": 100, "num": 3,
+                "timestamp": 1724054400, "guard_level": 3,
+                "batch_combo_id": "batch:gift:combo_id:1", "super_batch_gift_num": 3, "combo_total_coin": 300,
+                "medal_info": {
+                    "medal_name": "粉丝团", "medal_level": 12, "medal_color": 398668,
+                    "medal_color_start": 398668, "medal_color_end": 6850801,
+                    "anchor_uname": "streamer", "anchor_roomid": 21452505, "target_id": 2000
+                }
+            }
+        });
+
+        let LiveCommand::Gift(gift) = dispatch(&command, 21452505) else {
+            panic!("expected a gift");
+        };
+        assert_eq!(gift.gift_name, "小花花");
+        assert_eq!(gift.amount, 3);
+        assert_eq!(gift.timestamp, 1724054400000);
+        assert_eq!(gift.combo.unwrap().total_price, 300);
+        let badge = gift.user.badge.unwrap();
+        assert_eq!((badge.name.as_str(), badge.level), ("粉丝团", 12));
+        assert_eq!(badge.color, "#06154C");
+    }
+
+    #[test]
+    fn maps_history_danmu() {
+        let danmu: HistoryDanmu = serde_json::from_value(json!({
+            "text": "晚上好", "uid": 10001, "nickname": "viewer", "timeline": "2024-08-19 16:00:00",
+            "check_info": { "ts": 1724054400 }, "isadmin": 1, "guard_level": 2,
+            "medal": [21, "粉丝", "主播", 1, 6067854, "", 0, 6067854, 6067854, 6067854, 0, {}, 2]
+        }))
+        .unwrap();
+
+        let msg = history_danmu(danmu, 1);
+        let json = serde_json::to_value(&msg).unwrap();
+        assert_eq!(json["timestamp"], 1724054400000i64);
+        assert_eq!(json["history"], true);
+        assert_eq!(msg.user().badge.as_ref().unwrap().name, "粉丝");
+        assert!(msg.user().identity.as_ref().unwrap().room_admin);
+        assert!(msg.emoticon().is_none());
+    }
+
+    #[test]
+    fn maps_online_rank_events() {
+        let command =
+            json!({ "cmd": "ONLINE_RANK_COUNT", "data": { "count": 42, "online_count": 120 } });
+        assert!(matches!(
+            dispatch(&command, 1),
+            LiveCommand::OnlineRankCount(42)
+        ));
+
+        let command = json!({ "cmd": "ONLINE_RANK_V2", "data": { "list": [] } });
+        assert!(matches!(
+            dispatch(&command, 1),
+            LiveCommand::OnlineRankChanged
+        ));
+    }
+
+    #[test]
+    fn maps_user_actions() {
+        let command = json!({
+            "cmd": "INTERACT_WORD",
+            "data": {
+                "uid": 10001, "uname": "viewer", "msg_type": 2, "timestamp": 1724054400,
+                "fans_medal": { "medal_name": "", "medal_level": 0 }
+            }
+        });
+
+        let LiveCommand::UserAction(msg) = dispatch(&command, 1) else {
+            panic!("expected a user action");
+        };
+        assert_eq!(msg.action, "follow");
+        assert!(msg.user.badge.is_none());
+    }
+
+    #[test]
+    fn maps_header_updates() {
+        let command =
+            json!({ "cmd": "WATCHED_CHANGE", "data": { "num": 3145, "text_small": "3145" } });
+        assert!(matches!(dispatch(&command, 1), LiveCommand::Watcher(3145)));
+
+        let command = json!({ "cmd": "PREPARING", "roomid": "1" });
+        assert!(matches!(dispatch(&command, 1), LiveCommand::Live(false)));
+
+        let command = json!({
+            "cmd": "ROOM_CHANGE",
+            "data": { "title": "新标题", "area_name": "虚拟主播", "parent_area_name": "虚拟主播" }
+        });
+        let LiveCommand::RoomChange(change) = dispatch(&command, 1) else {
+            panic!("expected a room change");
+        };
+        assert_eq!(change.title, "新标题");
+    }
+
+    #[test]
+    fn collects_unknown_and_malformed_commands() {
+        let command = json!({ "cmd": "STOP_LIVE_ROOM_LIST", "data": {} });
+        assert!(
+            matches!(dispatch(&command, 1), LiveCommand::Unknown(cmd) if cmd == "STOP_LIVE_ROOM_LIST")
+        );
+
+        let command = json!({ "cmd": "GUARD_BUY", "data": { "uid": "oops" } });
+        assert!(matches!(dispatch(&command, 1), LiveCommand::Unknown(cmd) if cmd == "GUARD_BUY"));
+    }
+}
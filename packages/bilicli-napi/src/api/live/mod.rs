@@ -0,0 +1,675 @@
+use std::{collections::VecDeque, fmt, sync::Arc, time::Duration};
+
+use chrono::Local;
+
+use futures::{SinkExt, StreamExt};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tokio::{
+    net::TcpStream,
+    sync::Mutex,
+    time::{interval, sleep, Interval, MissedTickBehavior},
+};
+use tokio_tungstenite::{
+    client_async_tls,
+    tungstenite::{self, http::Uri, Message},
+    MaybeTlsStream, WebSocketStream,
+};
+
+use crate::{
+    api::{DanmuInfo, ProxyConfig, ProxyError, ProxyStream},
+    ui::{AppState, ConnectionState},
+    TuiState,
+};
+
+mod dispatch;
+mod packet;
+
+pub use dispatch::{dispatch, history_danmu, LiveCommand, RoomChange};
+pub use packet::{Operation, Packet, PacketError, ProtocolVersion, HEADER_LENGTH};
+
+pub const DEFAULT_LIVE_URL: &str = "wss://broadcastlv.chat.bilibili.com/sub";
+
+const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(30);
+const RECONNECT_MIN_DELAY: Duration = Duration::from_secs(1);
+const RECONNECT_MAX_DELAY: Duration = Duration::from_secs(60);
+
+#[derive(Clone, Debug)]
+pub struct LiveConfig {
+    /// candidate danmaku servers, tried in order
+    pub urls: Vec<String>,
+    pub room_id: u32,
+    pub uid: u64,
+    pub token: Option<String>,
+    /// tunnel the websocket through this proxy instead of connecting directly
+    pub proxy: Option<ProxyConfig>,
+    /// fetch the token and hosts from `getDanmuInfo` again before every round of hosts,
+    /// as the token expires
+    pub fetch_danmu_info: bool,
+}
+
+impl LiveConfig {
+    pub fn new(room_id: u32) -> Self {
+        Self {
+            urls: vec![DEFAULT_LIVE_URL.to_string()],
+            room_id,
+            uid: 0,
+            token: None,
+            proxy: None,
+            fetch_danmu_info: false,
+        }
+    }
+
+    /// use the token and hosts from `getDanmuInfo`, keeping the default server as a last resort
+    pub fn with_danmu_info(mut self, info: DanmuInfo) -> Self {
+        let mut urls: Vec<String> = info.host_list.iter().map(|host| host.wss_url()).collect();
+        if !urls.iter().any(|url| url == DEFAULT_LIVE_URL) {
+            urls.push(DEFAULT_LIVE_URL.to_string());
+        }
+
+        self.urls = urls;
+        self.token = Some(info.token);
+        self
+    }
+}
+
+#[derive(Serialize)]
+struct AuthBody<'a> {
+    uid: u64,
+    roomid: u32,
+    protover: u16,
+    platform: &'a str,
+    #[serde(rename = "type")]
+    kind: u8,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    key: Option<&'a str>,
+}
+
+#[derive(Deserialize)]
+struct AuthReply {
+    code: i32,
+}
+
+#[derive(Debug)]
+pub enum LiveEvent {
+    /// the room popularity carried by every heartbeat reply
+    Popularity(u32),
+    /// a raw command, e.g. `{"cmd":"DANMU_MSG","info":[...]}`
+    Command(Value),
+}
+
+#[derive(Debug)]
+pub enum LiveError {
+    WebSocket(tungstenite::Error),
+    Packet(PacketError),
+    Auth(String),
+    Proxy(ProxyError),
+    Closed,
+}
+
+impl fmt::Display for LiveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LiveError::WebSocket(err) => write!(f, "弹幕服务器连接错误: {}", err),
+            LiveError::Packet(err) => write!(f, "弹幕数据包错误: {}", err),
+            LiveError::Auth(message) => write!(f, "弹幕服务器认证失败: {}", message),
+            LiveError::Proxy(err) => write!(f, "{}", err),
+            LiveError::Closed => write!(f, "弹幕服务器连接已关闭"),
+        }
+    }
+}
+
+impl std::error::Error for LiveError {}
+
+impl From<tungstenite::Error> for LiveError {
+    fn from(err: tungstenite::Error) -> Self {
+        LiveError::WebSocket(err)
+    }
+}
+
+impl From<ProxyError> for LiveError {
+    fn from(err: ProxyError) -> Self {
+        LiveError::Proxy(err)
+    }
+}
+
+impl From<PacketError> for LiveError {
+    fn from(err: PacketError) -> Self {
+        LiveError::Packet(err)
+    }
+}
+
+pub struct LiveClient {
+    stream: WebSocketStream<MaybeTlsStream<ProxyStream>>,
+    heartbeat: Interval,
+    pending: VecDeque<LiveEvent>,
+    /// command packets skipped because their body is not JSON
+    invalid_packets: u32,
+}
+
+impl LiveClient {
+    /// connect to the danmaku server at `url` and join the room described by `config`
+    pub async fn connect(url: &str, config: &LiveConfig) -> Result<Self, LiveError> {
+        let uri: Uri = url
+            .parse()
+            .map_err(|err| LiveError::WebSocket(tungstenite::Error::from(err)))?;
+        let host = uri.host().unwrap_or_default();
+        let port = uri.port_u16().unwrap_or(match uri.scheme_str() {
+            Some("ws") => 80,
+            _ => 443,
+        });
+        let tunnel = match &config.proxy {
+            Some(proxy) if !proxy.bypasses(host) => proxy.connect(host, port).await?,
+            _ => TcpStream::connect((host, port))
+                .await
+                .map_err(tungstenite::Error::Io)?
+                .into(),
+        };
+        let (mut stream, _) = client_async_tls(url, tunnel).await?;
+
+        let auth = AuthBody {
+            uid: config.uid,
+            roomid: config.room_id,
+            protover: ProtocolVersion::Brotli as u16,
+            platform: "web",
+            kind: 2,
+            key: config.token.as_deref(),
+        };
+        let body = serde_json::to_vec(&auth).unwrap_or_default();
+        stream
+            .send(Message::Binary(Packet::new(Operation::Auth, body).encode()))
+            .await?;
+
+        loop {
+            match stream.next().await {
+                Some(Ok(Message::Binary(data))) => {
+                    let reply = Packet::decode_all(&data)?
+                        .into_iter()
+                        .find(|packet| packet.operation() == Some(Operation::AuthReply));
+
+                    if let Some(reply) = reply {
+                        match serde_json::from_slice::<AuthReply>(&reply.body) {
+                            Ok(AuthReply { code: 0 }) => break,
+                            Ok(AuthReply { code }) => {
+                                return Err(LiveError::Auth(format!("code {}", code)))
+                            }
+                            Err(err) => return Err(LiveError::Auth(err.to_string())),
+                        }
+                    }
+                }
+                Some(Ok(Message::Close(_))) | None => return Err(LiveError::Closed),
+                Some(Ok(_)) => {}
+                Some(Err(err)) => return Err(err.into()),
+            }
+        }
+
+        let mut heartbeat = interval(HEARTBEAT_INTERVAL);
+        heartbeat.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+        Ok(Self {
+            stream,
+            heartbeat,
+            pending: VecDeque::new(),
+            invalid_packets: 0,
+        })
+    }
+
+    /// wait for the next event, sending heartbeats in the meantime.
+    /// Returns `None` once the server closes the connection.
+    pub async fn next_event(&mut self) -> Option<Result<LiveEvent, LiveError>> {
+        loop {
+            if let Some(event) = self.pending.pop_front() {
+                return Some(Ok(event));
+            }
+
+            tokio::select! {
+                _ = self.heartbeat.tick() => {
+                    let heartbeat = Message::Binary(Packet::heartbeat().encode());
+                    if let Err(err) = self.stream.send(heartbeat).await {
+                        return Some(Err(err.into()));
+                    }
+                }
+                message = self.stream.next() => match message {
+                    Some(Ok(Message::Binary(data))) => {
+                        if let Err(err) = self.handle_binary(&data) {
+                            return Some(Err(err.into()));
+                        }
+                    }
+                    Some(Ok(Message::Close(_))) | None => return None,
+                    Some(Ok(_)) => {}
+                    Some(Err(err)) => return Some(Err(err.into())),
+                }
+            }
+        }
+    }
+
+    /// how many command packets were skipped because their body is not JSON
+    pub fn invalid_packets(&self) -> u32 {
+        self.invalid_packets
+    }
+
+    fn handle_binary(&mut self, data: &[u8]) -> Result<(), PacketError> {
+        for packet in Packet::decode_all(data)? {
+            match packet.operation() {
+                Some(Operation::HeartbeatReply) => {
+                    if let Some(popularity) = packet.popularity() {
+                        self.pending.push_back(LiveEvent::Popularity(popularity));
+                    }
+                }
+                // a bad command must not take the rest of the frame with it
+                Some(Operation::Command) => match packet.json() {
+                    Ok(command) => self.pending.push_back(LiveEvent::Command(command)),
+                    Err(_) => self.invalid_packets += 1,
+                },
+                _ => {}
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// exponential backoff between retries, e.g. reconnects or resent danmaku
+#[derive(Debug, Clone)]
+pub struct Backoff {
+    min: Duration,
+    max: Duration,
+    attempt: u32,
+}
+
+impl Backoff {
+    pub fn new(min: Duration, max: Duration) -> Self {
+        Self {
+            min,
+            max,
+            attempt: 0,
+        }
+    }
+
+    pub fn attempt(&self) -> u32 {
+        self.attempt
+    }
+
+    /// the delay before the next attempt, doubling each time up to `max`
+    pub fn next_delay(&mut self) -> Duration {
+        let delay = self
+            .min
+            .saturating_mul(2u32.saturating_pow(self.attempt))
+            .min(self.max);
+        self.attempt = self.attempt.saturating_add(1);
+        delay
+    }
+
+    pub fn reset(&mut self) {
+        self.attempt = 0;
+    }
+}
+
+/// feed every event of `client` into `state` until the connection ends
+pub async fn run_session(client: &mut LiveClient, state: &Mutex<TuiState>) -> LiveError {
+    while let Some(event) = client.next_event().await {
+        match event {
+            Ok(event) => state.lock().await.handle_live_event(event),
+            // a malformed frame only loses its own events
+            Err(LiveError::Packet(_)) => continue,
+            Err(err) => return err,
+        }
+    }
+
+    LiveError::Closed
+}
+
+/// keep the room connected until the app quits, moving to the next host whenever a
+/// connection fails, backing off exponentially after every failed round of hosts
+/// and marking every gap in the message list; the connection goes through the proxy
+/// of the state's api client, following `TuiState::set_proxy`
+pub async fn listen(mut config: LiveConfig, state: Arc<Mutex<TuiState>>) {
+    let mut backoff = Backoff::new(RECONNECT_MIN_DELAY, RECONNECT_MAX_DELAY);
+    let mut disconnected_at = None;
+    let mut host = 0;
+    let mut info_error = None;
+
+    loop {
+        if state.lock().await.state == AppState::Quit {
+            return;
+        }
+
+        let api = state.lock().await.api.clone();
+        if let Some(err) = api.proxy_error() {
+            // never fall back to a direct connection, wait for a usable proxy instead
+            let delay = backoff.next_delay();
+            let mut state = state.lock().await;
+            state.connection = ConnectionState::Reconnecting {
+                attempt: backoff.attempt(),
+            };
+            state.connection_error = Some(err.to_string());
+            drop(state);
+            sleep(delay).await;
+            continue;
+        }
+        config.proxy = api.proxy().cloned();
+
+        if host == 0 && config.fetch_danmu_info {
+            match api.get_danmu_info(config.room_id).await {
+                Ok(info) => {
+                    config = config.with_danmu_info(info);
+                    info_error = None;
+                }
+                // keep trying the hosts and token we have, which may still work
+                Err(err) => info_error = Some(format!("获取弹幕服务器信息失败: {}", err)),
+            }
+        }
+
+        let url = config
+            .urls
+            .get(host)
+            .map(String::as_str)
+            .unwrap_or(DEFAULT_LIVE_URL);
+
+        match LiveClient::connect(url, &config).await {
+            Ok(mut client) => {
+                backoff.reset();
+                {
+                    let mut state = state.lock().await;
+                    state.connection = ConnectionState::Connected;
+                    state.connection_error = None;
+                    if let Some(start) = disconnected_at.take() {
+                        state.mark_disconnected(start, Local::now().timestamp_millis());
+                    }
+                }
+
+                let err = run_session(&mut client, &state).await;
+                state.lock().await.connection_error = Some(err.to_string());
+                disconnected_at = Some(Local::now().timestamp_millis());
+                host = 0;
+            }
+            Err(err) => {
+                state.lock().await.connection_error = Some(match &info_error {
+                    Some(info_error) => format!("{}, {}", info_error, err),
+                    None => err.to_string(),
+                });
+                host = (host + 1) % config.urls.len().max(1);
+                if host != 0 {
+                    continue;
+                }
+            }
+        }
+
+        let delay = backoff.next_delay();
+        state.lock().await.connection = ConnectionState::Reconnecting {
+            attempt: backoff.attempt(),
+        };
+        sleep(delay).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+    use tokio::net::TcpListener;
+    use tokio_tungstenite::accept_async;
+
+    use super::*;
+    use crate::{api::ApiClient, ui::MsgType};
+
+    fn command_frame(command: Value) -> Message {
+        let body = serde_json::to_vec(&command).unwrap();
+        let mut packet = Packet::new(Operation::Command, body);
+        packet.protocol_version = 0;
+        Message::Binary(packet.encode())
+    }
+
+    /// a local danmaku server that accepts the auth packet and then replays `frames`,
+    /// once per element of `sessions`
+    async fn stand_in(sessions: Vec<Vec<Message>>) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("ws://{}/sub", listener.local_addr().unwrap());
+
+        tokio::spawn(async move {
+            for frames in sessions {
+                let (socket, _) = listener.accept().await.unwrap();
+                replay(socket, frames).await;
+            }
+        });
+
+        url
+    }
+
+    async fn replay(socket: TcpStream, frames: Vec<Message>) {
+        let mut ws = accept_async(socket).await.unwrap();
+
+        let Some(Ok(Message::Binary(auth))) = ws.next().await else {
+            panic!("expected an auth packet");
+        };
+        let auth = &Packet::decode_all(&auth).unwrap()[0];
+        assert_eq!(auth.operation(), Some(Operation::Auth));
+        let auth: Value = serde_json::from_slice(&auth.body).unwrap();
+        assert_eq!(auth["roomid"], 21452505);
+        assert_eq!(auth["key"], "token");
+
+        let reply = Packet::new(Operation::AuthReply, br#"{"code":0}"#.to_vec());
+        ws.send(Message::Binary(reply.encode())).await.unwrap();
+
+        for frame in frames {
+            ws.send(frame).await.unwrap();
+        }
+        ws.close(None).await.unwrap();
+    }
+
+    fn config(url: String) -> LiveConfig {
+        LiveConfig {
+            urls: vec![url],
+            room_id: 21452505,
+            uid: 0,
+            token: Some("token".to_string()),
+            proxy: None,
+            fetch_danmu_info: false,
+        }
+    }
+
+    #[tokio::test]
+    async fn replays_captured_frames_into_state() {
+        let mut heartbeat_reply =
+            Packet::new(Operation::HeartbeatReply, 1234u32.to_be_bytes().into());
+        heartbeat_reply.protocol_version = 1;
+
+        let url = stand_in(vec![vec![
+            Message::Binary(heartbeat_reply.encode()),
+            command_frame(json!({
+                "cmd": "DANMU_MSG",
+                "info": [
+                    [0, 1, 25, 16777215, 1724054400000i64, 0, 0, "", 0, 0, 0, "", 0, "{}", "{}", {}],
+                    "晚上好",
+                    [10001, "viewer", 0, 0, 0, 10000, 1, ""],
+                    [12, "粉丝团", "streamer", 21452505, 398668, "", 0, 6809855, 398668, 6850801, 0, 1, 2000],
+                    [20, 0, 6406234, ">50000", 0],
+                    ["", ""],
+                    0,
+                    0,
+                    null,
+                    {"ts": 1724054400, "ct": "ABCDEF"},
+                    0,
+                    0,
+                ]
+            })),
+            // a corrupt frame must not end the session
+            Message::Binary(vec![0, 0, 0, 42, 0, 16]),
+            command_frame(json!({ "cmd": "LIVE", "roomid": 21452505 })),
+        ]])
+        .await;
+
+        let state = Mutex::new(TuiState::new("21452505", None));
+        let mut client = LiveClient::connect(&url, &config(url.clone()))
+            .await
+            .unwrap();
+        run_session(&mut client, &state).await;
+
+        let state = state.lock().await;
+        assert!(state.is_live);
+        assert_eq!(state.messages.len(), 1);
+
+        let (t, msg) = &state.messages[0];
+        assert_eq!(*t, MsgType::Danmu);
+        let msg: Value = serde_json::from_str(msg).unwrap();
+        assert_eq!(msg["content"], "晚上好");
+        assert_eq!(msg["user"]["uid"], 10001);
+        assert_eq!(msg["user"]["badge"]["name"], "粉丝团");
+        assert_eq!(msg["user"]["badge"]["anchor"]["is_same_room"], true);
+    }
+
+    #[tokio::test]
+    async fn yields_heartbeat_popularity() {
+        let reply = Packet::new(Operation::HeartbeatReply, 42u32.to_be_bytes().into());
+        let url = stand_in(vec![vec![Message::Binary(reply.encode())]]).await;
+
+        let mut client = LiveClient::connect(&url, &config(url.clone()))
+            .await
+            .unwrap();
+        match client.next_event().await {
+            Some(Ok(LiveEvent::Popularity(popularity))) => assert_eq!(popularity, 42),
+            event => panic!("unexpected event {:?}", event),
+        }
+        assert!(client.next_event().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn skips_bad_packets_within_a_frame() {
+        let packet = |body: &[u8]| {
+            let mut packet = Packet::new(Operation::Command, body.to_vec());
+            packet.protocol_version = 0;
+            packet.encode()
+        };
+        let frame = [
+            packet(br#"{"cmd":"PREPARING"}"#),
+            packet(b"{not json"),
+            packet(br#"{"cmd":"LIVE"}"#),
+        ]
+        .concat();
+        let url = stand_in(vec![vec![Message::Binary(frame)]]).await;
+
+        let mut client = LiveClient::connect(&url, &config(url.clone()))
+            .await
+            .unwrap();
+        let mut commands = vec![];
+        while let Some(Ok(LiveEvent::Command(command))) = client.next_event().await {
+            commands.push(command["cmd"].as_str().unwrap().to_string());
+        }
+
+        assert_eq!(commands, ["PREPARING", "LIVE"]);
+        assert_eq!(client.invalid_packets(), 1);
+    }
+
+    #[test]
+    fn backoff_doubles_up_to_max() {
+        let mut backoff = Backoff::new(Duration::from_secs(1), Duration::from_secs(5));
+        let delays: Vec<u64> = (0..5).map(|_| backoff.next_delay().as_secs()).collect();
+        assert_eq!(delays, [1, 2, 4, 5, 5]);
+
+        backoff.reset();
+        assert_eq!(backoff.next_delay(), Duration::from_secs(1));
+    }
+
+    #[tokio::test]
+    async fn reconnects_and_marks_the_gap() {
+        let url = stand_in(vec![
+            vec![command_frame(json!({ "cmd": "PREPARING" }))],
+            vec![command_frame(json!({ "cmd": "LIVE" }))],
+        ])
+        .await;
+
+        let state = Arc::new(Mutex::new(TuiState::new("21452505", None)));
+        let task = tokio::spawn(listen(config(url), Arc::clone(&state)));
+
+        for _ in 0..100 {
+            if state.lock().await.is_live {
+                break;
+            }
+            sleep(Duration::from_millis(50)).await;
+        }
+        task.abort();
+
+        let state = state.lock().await;
+        assert!(state.is_live);
+        assert_eq!(state.messages.len(), 1);
+        assert_eq!(state.messages[0].0, MsgType::Disconnect);
+    }
+
+    #[tokio::test]
+    async fn fails_over_to_the_next_host() {
+        let dead = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let dead_url = format!("ws://{}/sub", dead.local_addr().unwrap());
+        drop(dead);
+
+        let url = stand_in(vec![vec![command_frame(json!({ "cmd": "LIVE" }))]]).await;
+        let mut config = config(url);
+        config.urls.insert(0, dead_url);
+
+        let state = Arc::new(Mutex::new(TuiState::new("21452505", None)));
+        let task = tokio::spawn(listen(config, Arc::clone(&state)));
+
+        for _ in 0..100 {
+            if state.lock().await.is_live {
+                break;
+            }
+            sleep(Duration::from_millis(50)).await;
+        }
+        task.abort();
+
+        assert!(state.lock().await.is_live);
+    }
+
+    #[tokio::test]
+    async fn reports_danmu_info_failures() {
+        let mut server = mockito::Server::new_async().await;
+        let mock = server
+            .mock(
+                "GET",
+                "/xlive/web-room/v1/index/getDanmuInfo?id=21452505&type=0",
+            )
+            .with_status(412)
+            .expect_at_least(1)
+            .create_async()
+            .await;
+
+        let dead = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let mut config = config(format!("ws://{}/sub", dead.local_addr().unwrap()));
+        config.fetch_danmu_info = true;
+        drop(dead);
+
+        let mut tui_state = TuiState::new("21452505", None);
+        tui_state.api = ApiClient::builder().base_url(server.url()).build();
+        let state = Arc::new(Mutex::new(tui_state));
+        let task = tokio::spawn(listen(config, Arc::clone(&state)));
+
+        for _ in 0..100 {
+            if state.lock().await.connection_error.is_some() {
+                break;
+            }
+            sleep(Duration::from_millis(50)).await;
+        }
+        task.abort();
+
+        mock.assert_async().await;
+        let error = state.lock().await.connection_error.clone().unwrap();
+        assert!(error.starts_with("获取弹幕服务器信息失败"));
+    }
+
+    #[test]
+    fn builds_hosts_from_danmu_info() {
+        let info: DanmuInfo = serde_json::from_value(json!({
+            "token": "abc",
+            "host_list": [{ "host": "tx-bj-live-comet.chat.bilibili.com", "port": 2243, "wss_port": 443, "ws_port": 2244 }]
+        }))
+        .unwrap();
+
+        let config = LiveConfig::new(1).with_danmu_info(info);
+        assert_eq!(config.token.as_deref(), Some("abc"));
+        assert_eq!(
+            config.urls,
+            [
+                "wss://tx-bj-live-comet.chat.bilibili.com:443/sub",
+                DEFAULT_LIVE_URL
+            ]
+        );
+    }
+}
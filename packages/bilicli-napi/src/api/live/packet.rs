@@ -0,0 +1,416 @@
+use std::{fmt, io::Read};
+
+use flate2::read::ZlibDecoder;
+use serde_json::Value;
+
+pub const HEADER_LENGTH: usize = 16;
+
+/// upper bound for an inflated body, so a malicious frame cannot exhaust memory
+pub const MAX_INFLATED_LENGTH: u64 = 16 * 1024 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Operation {
+    Heartbeat = 2,
+    HeartbeatReply = 3,
+    Command = 5,
+    Auth = 7,
+    AuthReply = 8,
+}
+
+impl Operation {
+    pub fn from_u32(value: u32) -> Option<Self> {
+        match value {
+            2 => Some(Operation::Heartbeat),
+            3 => Some(Operation::HeartbeatReply),
+            5 => Some(Operation::Command),
+            7 => Some(Operation::Auth),
+            8 => Some(Operation::AuthReply),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProtocolVersion {
+    /// plain JSON body
+    Json = 0,
+    /// a big-endian `u32` body, or JSON for auth packets
+    Int32 = 1,
+    /// zlib compressed packets
+    Zlib = 2,
+    /// brotli compressed packets
+    Brotli = 3,
+}
+
+impl ProtocolVersion {
+    pub fn from_u16(value: u16) -> Option<Self> {
+        match value {
+            0 => Some(ProtocolVersion::Json),
+            1 => Some(ProtocolVersion::Int32),
+            2 => Some(ProtocolVersion::Zlib),
+            3 => Some(ProtocolVersion::Brotli),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PacketError {
+    /// the buffer ends before the header or the body it announces
+    Truncated {
+        expected: usize,
+        actual: usize,
+    },
+    InvalidHeaderLength(u16),
+    InvalidPacketLength(u32),
+    UnknownProtocolVersion(u16),
+    /// a compressed body is corrupt or inflates beyond `MAX_INFLATED_LENGTH`
+    Inflate(String),
+    /// a compressed body contains compressed packets again
+    NestedCompression,
+    InvalidJson(String),
+}
+
+impl fmt::Display for PacketError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PacketError::Truncated { expected, actual } => {
+                write!(
+                    f,
+                    "数据包不完整: 需要 {} 字节, 实际 {} 字节",
+                    expected, actual
+                )
+            }
+            PacketError::InvalidHeaderLength(length) => write!(f, "无效的包头长度: {}", length),
+            PacketError::InvalidPacketLength(length) => write!(f, "无效的数据包长度: {}", length),
+            PacketError::UnknownProtocolVersion(version) => {
+                write!(f, "未知的协议版本: {}", version)
+            }
+            PacketError::Inflate(message) => write!(f, "解压数据包失败: {}", message),
+            PacketError::NestedCompression => write!(f, "数据包嵌套压缩"),
+            PacketError::InvalidJson(message) => write!(f, "解析数据包 JSON 失败: {}", message),
+        }
+    }
+}
+
+impl std::error::Error for PacketError {}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Packet {
+    pub header_length: u16,
+    pub protocol_version: u16,
+    pub operation: u32,
+    pub sequence: u32,
+    pub body: Vec<u8>,
+}
+
+impl Packet {
+    pub fn new(operation: Operation, body: Vec<u8>) -> Self {
+        Self {
+            header_length: HEADER_LENGTH as u16,
+            protocol_version: ProtocolVersion::Int32 as u16,
+            operation: operation as u32,
+            sequence: 1,
+            body,
+        }
+    }
+
+    pub fn heartbeat() -> Self {
+        Self::new(Operation::Heartbeat, b"[object Object]".to_vec())
+    }
+
+    pub fn operation(&self) -> Option<Operation> {
+        Operation::from_u32(self.operation)
+    }
+
+    pub fn protocol_version(&self) -> Option<ProtocolVersion> {
+        ProtocolVersion::from_u16(self.protocol_version)
+    }
+
+    pub fn encode(&self) -> Vec<u8> {
+        let header_length = (self.header_length as usize).max(HEADER_LENGTH);
+        let packet_length = (header_length + self.body.len()) as u32;
+        let mut buf = Vec::with_capacity(packet_length as usize);
+        buf.extend_from_slice(&packet_length.to_be_bytes());
+        buf.extend_from_slice(&(header_length as u16).to_be_bytes());
+        buf.extend_from_slice(&self.protocol_version.to_be_bytes());
+        buf.extend_from_slice(&self.operation.to_be_bytes());
+        buf.extend_from_slice(&self.sequence.to_be_bytes());
+        buf.resize(header_length, 0);
+        buf.extend_from_slice(&self.body);
+        buf
+    }
+
+    /// decode the first packet in `buf`, returning it with the number of bytes consumed
+    pub fn decode(buf: &[u8]) -> Result<(Packet, usize), PacketError> {
+        if buf.len() < HEADER_LENGTH {
+            return Err(PacketError::Truncated {
+                expected: HEADER_LENGTH,
+                actual: buf.len(),
+            });
+        }
+
+        let packet_length = u32::from_be_bytes([buf[0], buf[1], buf[2], buf[3]]);
+        let header_length = u16::from_be_bytes([buf[4], buf[5]]);
+        if (header_length as usize) < HEADER_LENGTH {
+            return Err(PacketError::InvalidHeaderLength(header_length));
+        }
+        if (packet_length as usize) < header_length as usize {
+            return Err(PacketError::InvalidPacketLength(packet_length));
+        }
+        if packet_length as usize > buf.len() {
+            return Err(PacketError::Truncated {
+                expected: packet_length as usize,
+                actual: buf.len(),
+            });
+        }
+
+        let packet = Packet {
+            header_length,
+            protocol_version: u16::from_be_bytes([buf[6], buf[7]]),
+            operation: u32::from_be_bytes([buf[8], buf[9], buf[10], buf[11]]),
+            sequence: u32::from_be_bytes([buf[12], buf[13], buf[14], buf[15]]),
+            body: buf[header_length as usize..packet_length as usize].to_vec(),
+        };
+
+        Ok((packet, packet_length as usize))
+    }
+
+    /// split concatenated packets without touching their bodies
+    pub fn split(mut buf: &[u8]) -> Result<Vec<Packet>, PacketError> {
+        let mut packets = vec![];
+
+        while !buf.is_empty() {
+            let (packet, length) = Self::decode(buf)?;
+            packets.push(packet);
+            buf = &buf[length..];
+        }
+
+        Ok(packets)
+    }
+
+    /// split concatenated packets and replace every compressed packet with the packets inside it
+    pub fn decode_all(buf: &[u8]) -> Result<Vec<Packet>, PacketError> {
+        let mut packets = vec![];
+
+        for packet in Self::split(buf)? {
+            match packet.protocol_version() {
+                Some(ProtocolVersion::Zlib | ProtocolVersion::Brotli) => {
+                    for inner in Self::split(&packet.inflate()?)? {
+                        if matches!(
+                            inner.protocol_version(),
+                            Some(ProtocolVersion::Zlib | ProtocolVersion::Brotli)
+                        ) {
+                            return Err(PacketError::NestedCompression);
+                        }
+                        packets.push(inner);
+                    }
+                }
+                Some(_) => packets.push(packet),
+                None => return Err(PacketError::UnknownProtocolVersion(packet.protocol_version)),
+            }
+        }
+
+        Ok(packets)
+    }
+
+    /// decode `buf` and collect the JSON of every command packet in it
+    pub fn decode_commands(buf: &[u8]) -> Result<Vec<Value>, PacketError> {
+        Self::decode_all(buf)?
+            .into_iter()
+            .filter(|packet| packet.operation() == Some(Operation::Command))
+            .map(|packet| packet.json())
+            .collect()
+    }
+
+    pub fn json(&self) -> Result<Value, PacketError> {
+        serde_json::from_slice(&self.body).map_err(|err| PacketError::InvalidJson(err.to_string()))
+    }
+
+    /// the popularity carried by a heartbeat reply
+    pub fn popularity(&self) -> Option<u32> {
+        let bytes = self.body.get(..4)?;
+        Some(u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+
+    fn inflate(&self) -> Result<Vec<u8>, PacketError> {
+        let mut inflated = vec![];
+        let result = match self.protocol_version() {
+            Some(ProtocolVersion::Zlib) => ZlibDecoder::new(self.body.as_slice())
+                .take(MAX_INFLATED_LENGTH + 1)
+                .read_to_end(&mut inflated),
+            Some(ProtocolVersion::Brotli) => brotli::Decompressor::new(self.body.as_slice(), 4096)
+                .take(MAX_INFLATED_LENGTH + 1)
+                .read_to_end(&mut inflated),
+            _ => return Ok(self.body.clone()),
+        };
+
+        result.map_err(|err| PacketError::Inflate(err.to_string()))?;
+        if inflated.len() as u64 > MAX_INFLATED_LENGTH {
+            return Err(PacketError::Inflate("数据包过大".to_string()));
+        }
+
+        Ok(inflated)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use flate2::{write::ZlibEncoder, Compression};
+    use serde_json::json;
+
+    use super::*;
+
+    fn command(cmd: &str) -> Packet {
+        let mut packet = Packet::new(Operation::Command, json!({ "cmd": cmd }).to_string().into());
+        packet.protocol_version = ProtocolVersion::Json as u16;
+        packet
+    }
+
+    fn compressed(version: ProtocolVersion, packets: &[Packet]) -> Packet {
+        let raw: Vec<u8> = packets.iter().flat_map(Packet::encode).collect();
+        let body = match version {
+            ProtocolVersion::Zlib => {
+                let mut encoder = ZlibEncoder::new(vec![], Compression::default());
+                encoder.write_all(&raw).unwrap();
+                encoder.finish().unwrap()
+            }
+            ProtocolVersion::Brotli => {
+                let mut body = vec![];
+                let mut writer = brotli::CompressorWriter::new(&mut body, 4096, 5, 22);
+                writer.write_all(&raw).unwrap();
+                drop(writer);
+                body
+            }
+            _ => raw,
+        };
+
+        let mut packet = Packet::new(Operation::Command, body);
+        packet.protocol_version = version as u16;
+        packet
+    }
+
+    fn cmds(buf: &[u8]) -> Vec<String> {
+        Packet::decode_commands(buf)
+            .unwrap()
+            .into_iter()
+            .map(|command| command["cmd"].as_str().unwrap().to_string())
+            .collect()
+    }
+
+    #[test]
+    fn encode_decode_roundtrip() {
+        let packet = Packet::new(Operation::Auth, br#"{"roomid":1}"#.to_vec());
+        let buf = packet.encode();
+
+        assert_eq!(buf.len(), HEADER_LENGTH + 12);
+        assert_eq!(&buf[..4], &28u32.to_be_bytes());
+        assert_eq!(Packet::decode(&buf).unwrap(), (packet, buf.len()));
+    }
+
+    #[test]
+    fn skips_extended_header() {
+        let mut packet = command("LIVE");
+        packet.header_length = 20;
+        let buf = packet.encode();
+
+        assert_eq!(Packet::decode(&buf).unwrap().0, packet);
+        assert_eq!(cmds(&buf), ["LIVE"]);
+    }
+
+    #[test]
+    fn splits_concatenated_packets() {
+        let mut buf = command("LIVE").encode();
+        buf.extend(command("PREPARING").encode());
+        buf.extend(Packet::new(Operation::HeartbeatReply, 7u32.to_be_bytes().into()).encode());
+
+        let packets = Packet::split(&buf).unwrap();
+        assert_eq!(packets.len(), 3);
+        assert_eq!(packets[2].popularity(), Some(7));
+        assert_eq!(cmds(&buf), ["LIVE", "PREPARING"]);
+    }
+
+    #[test]
+    fn inflates_zlib_and_brotli_bodies() {
+        let inner = [command("DANMU_MSG"), command("SEND_GIFT")];
+
+        let zlib = compressed(ProtocolVersion::Zlib, &inner).encode();
+        assert_eq!(cmds(&zlib), ["DANMU_MSG", "SEND_GIFT"]);
+
+        let brotli = compressed(ProtocolVersion::Brotli, &inner).encode();
+        assert_eq!(cmds(&brotli), ["DANMU_MSG", "SEND_GIFT"]);
+
+        let mut both = zlib;
+        both.extend(brotli);
+        assert_eq!(Packet::decode_all(&both).unwrap().len(), 4);
+    }
+
+    #[test]
+    fn rejects_truncated_packets() {
+        let buf = command("LIVE").encode();
+
+        assert_eq!(
+            Packet::decode(&buf[..10]),
+            Err(PacketError::Truncated {
+                expected: HEADER_LENGTH,
+                actual: 10
+            })
+        );
+        assert!(matches!(
+            Packet::split(&buf[..buf.len() - 1]),
+            Err(PacketError::Truncated { .. })
+        ));
+    }
+
+    #[test]
+    fn rejects_invalid_lengths() {
+        let mut buf = command("LIVE").encode();
+        buf[4..6].copy_from_slice(&4u16.to_be_bytes());
+        assert_eq!(
+            Packet::decode(&buf),
+            Err(PacketError::InvalidHeaderLength(4))
+        );
+
+        let mut buf = command("LIVE").encode();
+        buf[..4].copy_from_slice(&8u32.to_be_bytes());
+        assert_eq!(
+            Packet::decode(&buf),
+            Err(PacketError::InvalidPacketLength(8))
+        );
+    }
+
+    #[test]
+    fn rejects_bad_bodies() {
+        let mut packet = command("LIVE");
+        packet.protocol_version = 9;
+        assert_eq!(
+            Packet::decode_all(&packet.encode()),
+            Err(PacketError::UnknownProtocolVersion(9))
+        );
+
+        let mut packet = command("LIVE");
+        packet.protocol_version = ProtocolVersion::Zlib as u16;
+        assert!(matches!(
+            Packet::decode_all(&packet.encode()),
+            Err(PacketError::Inflate(_))
+        ));
+
+        let nested = compressed(
+            ProtocolVersion::Zlib,
+            &[compressed(ProtocolVersion::Zlib, &[command("LIVE")])],
+        );
+        assert_eq!(
+            Packet::decode_all(&nested.encode()),
+            Err(PacketError::NestedCompression)
+        );
+
+        let mut packet = command("LIVE");
+        packet.body = b"{not json".to_vec();
+        assert!(matches!(
+            Packet::decode_commands(&packet.encode()),
+            Err(PacketError::InvalidJson(_))
+        ));
+    }
+}
@@ -1,9 +1,68 @@
+mod client;
+mod error;
+mod get_area_list;
+mod get_danmu_config;
+mod get_danmu_info;
+mod get_emoticons;
+mod get_gift_config;
+mod get_guard_list;
+mod get_history;
 mod get_info;
+mod get_online_rank;
+mod get_room_by_uid;
 mod get_room_by_user;
+mod get_user_card;
+mod moderation;
+mod proxy;
+mod room_init;
+mod search_streamer;
 mod send_danmu;
+mod update_room;
 
-pub use get_info::get_room_info;
+pub mod live;
+
+pub use client::ApiClient;
+pub use client::ApiClientBuilder;
+pub use client::API_BASE_URL;
+pub use client::MAIN_API_BASE_URL;
+pub use error::ApiError;
+pub use error::ApiResult;
+pub use error::BusinessError;
+pub use get_area_list::Area;
+pub use get_area_list::AreaParent;
+pub use get_danmu_config::DanmuColor;
+pub use get_danmu_config::DanmuConfig;
+pub use get_danmu_config::DanmuModeOption;
+pub use get_danmu_info::DanmuHost;
+pub use get_danmu_info::DanmuInfo;
+pub use get_emoticons::EmoticonPackage;
+pub use get_emoticons::RoomEmoticon;
+pub use get_gift_config::GiftConfig;
+pub use get_guard_list::GuardMedal;
+pub use get_guard_list::GuardMember;
+pub use get_guard_list::GuardPage;
+pub use get_guard_list::GuardPageInfo;
+pub use get_history::HistoryDanmu;
 pub use get_info::RoomInfo;
-pub use get_room_by_user::get_room_by_user;
+pub use get_online_rank::OnlineRank;
+pub use get_online_rank::OnlineRankUser;
+pub use get_online_rank::RankMedal;
+pub use get_room_by_uid::StreamerRoom;
+pub use get_room_by_user::RoomUserInfo;
 pub use get_room_by_user::WearedV2;
-pub use send_danmu::send_danmu;
+pub use get_user_card::CardInfo;
+pub use get_user_card::LevelInfo;
+pub use get_user_card::UserCard;
+pub use moderation::MuteDuration;
+pub use moderation::SilentUser;
+pub use moderation::SilentUserPage;
+pub use proxy::ProxyConfig;
+pub use proxy::ProxyError;
+pub use proxy::ProxyScheme;
+pub use proxy::ProxyStream;
+pub use room_init::parse_room_input;
+pub use room_init::RoomInit;
+pub use search_streamer::StreamerCandidate;
+pub use send_danmu::DanmuMode;
+pub use send_danmu::SendDanmuData;
+pub use send_danmu::SendDanmuOptions;
@@ -0,0 +1,225 @@
+use std::collections::HashMap;
+
+use serde::{de::IgnoredAny, Deserialize};
+
+use super::{ApiClient, ApiResult};
+
+/// stop paging the muted viewers of huge rooms after this many pages
+const MAX_PAGES: u32 = 20;
+
+/// how long a viewer is muted in the room
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum MuteDuration {
+    /// until the current live ends
+    CurrentLive,
+    Hours(u32),
+    /// permanently; the live admin API has no room blacklist apart from this, the
+    /// blacklist of the main site only hides a viewer from the account, not the room
+    Forever,
+}
+
+impl MuteDuration {
+    /// the `hour` form value the admin API expects
+    fn hour(self) -> i64 {
+        match self {
+            MuteDuration::CurrentLive => 0,
+            MuteDuration::Hours(hours) => hours as i64,
+            MuteDuration::Forever => -1,
+        }
+    }
+
+    pub fn name(self) -> String {
+        match self {
+            MuteDuration::CurrentLive => "本场直播".to_string(),
+            MuteDuration::Hours(hours) if hours % 24 == 0 => format!("{} 天", hours / 24),
+            MuteDuration::Hours(hours) => format!("{} 小时", hours),
+            MuteDuration::Forever => "永久".to_string(),
+        }
+    }
+}
+
+#[derive(Clone, Deserialize, Debug)]
+pub struct SilentUser {
+    /// the id of the mute record, needed to lift it
+    pub id: u64,
+    pub tuid: u64,
+    pub tname: String,
+    /// the admin who muted the viewer
+    #[serde(default)]
+    pub name: String,
+    #[serde(default)]
+    pub ctime: String,
+}
+
+#[derive(Clone, Deserialize, Debug, Default)]
+pub struct SilentUserPage {
+    #[serde(default)]
+    pub data: Vec<SilentUser>,
+    #[serde(default)]
+    pub total: u32,
+    #[serde(default)]
+    pub total_page: u32,
+}
+
+impl ApiClient {
+    /// mute `uid` in `room_id`; `msg` is the offending danmaku, shown to other admins
+    pub async fn add_silent_user(
+        &self,
+        room_id: u32,
+        uid: u64,
+        duration: MuteDuration,
+        msg: Option<&str>,
+    ) -> ApiResult<()> {
+        let csrf = self.require_csrf()?;
+
+        let mut params = HashMap::new();
+        params.insert("room_id", room_id.to_string());
+        params.insert("tuid", uid.to_string());
+        params.insert("hour", duration.hour().to_string());
+        params.insert("msg", msg.unwrap_or_default().to_string());
+        params.insert("mobile_app", "web".to_string());
+        params.insert("csrf", csrf.to_string());
+        params.insert("csrf_token", csrf.to_string());
+
+        let _: IgnoredAny = self
+            .send(
+                self.post("/xlive/web-ucenter/v1/banned/AddSilentUser")
+                    .form(&params),
+            )
+            .await?;
+        Ok(())
+    }
+
+    /// one page of the viewers muted in `room_id`, starting at page `1`
+    pub async fn get_silent_users(&self, room_id: u32, page: u32) -> ApiResult<SilentUserPage> {
+        let csrf = self.require_csrf()?;
+
+        let mut params = HashMap::new();
+        params.insert("room_id", room_id.to_string());
+        params.insert("ps", page.to_string());
+        params.insert("csrf", csrf.to_string());
+        params.insert("csrf_token", csrf.to_string());
+
+        self.send(
+            self.post("/xlive/web-ucenter/v1/banned/GetSilentUserList")
+                .form(&params),
+        )
+        .await
+    }
+
+    /// every viewer muted in `room_id`, in one page
+    pub async fn get_all_silent_users(&self, room_id: u32) -> ApiResult<SilentUserPage> {
+        let mut first = self.get_silent_users(room_id, 1).await?;
+        for page in 2..=first.total_page.min(MAX_PAGES) {
+            first
+                .data
+                .extend(self.get_silent_users(room_id, page).await?.data);
+        }
+
+        Ok(first)
+    }
+
+    /// lift the mute record `id` from `get_silent_users`
+    pub async fn remove_silent_user(&self, room_id: u32, id: u64) -> ApiResult<()> {
+        let csrf = self.require_csrf()?;
+
+        let mut params = HashMap::new();
+        params.insert("roomid", room_id.to_string());
+        params.insert("id", id.to_string());
+        params.insert("csrf", csrf.to_string());
+        params.insert("csrf_token", csrf.to_string());
+
+        let _: IgnoredAny = self
+            .send(
+                self.post("/banned_service/v1/Silent/del_room_block_user")
+                    .form(&params),
+            )
+            .await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use mockito::Matcher;
+
+    use super::*;
+    use crate::api::BusinessError;
+
+    fn client(server: &mockito::Server) -> ApiClient {
+        ApiClient::builder()
+            .base_url(server.url())
+            .cookie(Some("SESSDATA=abc; bili_jct=def".to_string()))
+            .build()
+    }
+
+    #[tokio::test]
+    async fn mutes_and_lists_users() {
+        let mut server = mockito::Server::new_async().await;
+        let mute = server
+            .mock("POST", "/xlive/web-ucenter/v1/banned/AddSilentUser")
+            .match_body(Matcher::AllOf(vec![
+                Matcher::UrlEncoded("tuid".into(), "10086".into()),
+                Matcher::UrlEncoded("hour".into(), "-1".into()),
+                Matcher::UrlEncoded("csrf".into(), "def".into()),
+            ]))
+            .with_body(r#"{"code":0,"message":"0","data":{}}"#)
+            .create_async()
+            .await;
+        server
+            .mock("POST", "/xlive/web-ucenter/v1/banned/GetSilentUserList")
+            .with_body(
+                r#"{"code":0,"message":"0","data":{"data":[
+                    {"tuid":10086,"tname":"spammer","uid":1,"name":"admin",
+                     "ctime":"2024-01-01 12:00:00","id":42,"is_anchor":0,"face":"","admin_level":1}
+                ],"total":1,"total_page":1}}"#,
+            )
+            .create_async()
+            .await;
+
+        let api = client(&server);
+        api.add_silent_user(1, 10086, MuteDuration::Forever, None)
+            .await
+            .unwrap();
+        let page = api.get_silent_users(1, 1).await.unwrap();
+
+        mute.assert_async().await;
+        assert_eq!(page.data[0].id, 42);
+        assert_eq!(page.data[0].tname, "spammer");
+    }
+
+    #[tokio::test]
+    async fn lists_every_page() {
+        let mut server = mockito::Server::new_async().await;
+        for page in 1..=2 {
+            server
+                .mock("POST", "/xlive/web-ucenter/v1/banned/GetSilentUserList")
+                .match_body(Matcher::UrlEncoded("ps".into(), page.to_string()))
+                .with_body(format!(
+                    r#"{{"code":0,"message":"0","data":{{"data":[
+                        {{"tuid":{page},"tname":"user{page}","id":{page}}}
+                    ],"total":2,"total_page":2}}}}"#
+                ))
+                .create_async()
+                .await;
+        }
+
+        let page = client(&server).get_all_silent_users(1).await.unwrap();
+        assert_eq!(page.total, 2);
+        let names: Vec<_> = page.data.iter().map(|user| user.tname.as_str()).collect();
+        assert_eq!(names, ["user1", "user2"]);
+    }
+
+    #[tokio::test]
+    async fn reports_missing_permission() {
+        let mut server = mockito::Server::new_async().await;
+        server
+            .mock("POST", "/banned_service/v1/Silent/del_room_block_user")
+            .with_body(r#"{"code":-403,"message":"没有权限","data":[]}"#)
+            .create_async()
+            .await;
+
+        let err = client(&server).remove_silent_user(1, 42).await.unwrap_err();
+        assert_eq!(err.kind(), Some(BusinessError::Forbidden));
+    }
+}
@@ -0,0 +1,105 @@
+use serde::Deserialize;
+
+use super::{ApiClient, ApiError, ApiResult};
+
+#[derive(Clone, Deserialize, Debug)]
+pub struct RoomInit {
+    pub room_id: u32,
+    pub short_id: u32,
+    pub uid: u64,
+    pub live_status: u32,
+    pub is_hidden: bool,
+    pub is_locked: bool,
+    pub encrypted: bool,
+}
+
+/// parse a room number or a `live.bilibili.com` url into a (possibly short) room id
+pub fn parse_room_input(input: &str) -> Option<u32> {
+    let input = input.trim();
+    if let Ok(id) = input.parse() {
+        return Some(id);
+    }
+
+    let rest = input
+        .strip_prefix("https://")
+        .or_else(|| input.strip_prefix("http://"))
+        .unwrap_or(input);
+    let rest = rest.split(['?', '#']).next()?;
+    let (host, path) = rest.split_once('/')?;
+    if host != "live.bilibili.com" && !host.ends_with(".live.bilibili.com") {
+        return None;
+    }
+
+    path.split('/')
+        .rev()
+        .find(|segment| !segment.is_empty())?
+        .parse()
+        .ok()
+}
+
+impl ApiClient {
+    /// look up the canonical room id and short id of `room_id`, which may be either of both
+    pub async fn room_init(&self, room_id: u32) -> ApiResult<RoomInit> {
+        self.send(self.get(&format!("/room/v1/Room/room_init?id={}", room_id)))
+            .await
+    }
+
+    /// resolve a short id, long id or room url to the canonical room
+    pub async fn resolve_room(&self, input: &str) -> ApiResult<RoomInit> {
+        let room_id =
+            parse_room_input(input).ok_or_else(|| ApiError::InvalidRoom(input.to_string()))?;
+        self.room_init(room_id).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_ids_and_urls() {
+        assert_eq!(parse_room_input("510"), Some(510));
+        assert_eq!(parse_room_input(" 21452505 "), Some(21452505));
+        assert_eq!(parse_room_input("https://live.bilibili.com/510"), Some(510));
+        assert_eq!(
+            parse_room_input("https://live.bilibili.com/h5/21452505?broadcast_type=0"),
+            Some(21452505)
+        );
+        assert_eq!(parse_room_input("live.bilibili.com/blanc/510/"), Some(510));
+        assert_eq!(parse_room_input("https://space.bilibili.com/510"), None);
+        assert_eq!(parse_room_input("abc"), None);
+    }
+
+    #[tokio::test]
+    async fn resolves_short_id() {
+        let mut server = mockito::Server::new_async().await;
+        let mock = server
+            .mock("GET", "/room/v1/Room/room_init?id=510")
+            .with_body(
+                r#"{"code":0,"msg":"ok","message":"ok","data":{"room_id":80397,"short_id":510,
+                    "uid":11153765,"need_p2p":0,"is_hidden":false,"is_locked":false,
+                    "is_portrait":false,"live_status":1,"hidden_till":0,"lock_till":0,
+                    "encrypted":false,"pwd_verified":false,"live_time":1700000000,
+                    "room_shield":0,"is_sp":0,"special_type":0}}"#,
+            )
+            .create_async()
+            .await;
+
+        let api = ApiClient::builder().base_url(server.url()).build();
+        let room = api
+            .resolve_room("https://live.bilibili.com/510")
+            .await
+            .unwrap();
+
+        mock.assert_async().await;
+        assert_eq!(room.room_id, 80397);
+        assert_eq!(room.short_id, 510);
+    }
+
+    #[tokio::test]
+    async fn rejects_unknown_input() {
+        let api = ApiClient::builder().base_url("http://127.0.0.1:1").build();
+        let err = api.resolve_room("not a room").await.unwrap_err();
+        assert!(matches!(err, ApiError::InvalidRoom(_)));
+    }
+}
@@ -0,0 +1,108 @@
+use serde::Deserialize;
+
+use super::{ApiClient, ApiResult};
+
+#[derive(Clone, Deserialize, Debug)]
+pub struct StreamerCandidate {
+    pub uid: u64,
+    /// the name with the search highlight markup removed
+    pub uname: String,
+    #[serde(rename = "roomid")]
+    pub room_id: u32,
+    #[serde(default)]
+    pub is_live: bool,
+    #[serde(default)]
+    pub attentions: u32,
+}
+
+#[derive(Deserialize)]
+struct SearchResult {
+    #[serde(default)]
+    result: Vec<StreamerCandidate>,
+}
+
+/// drop the `<em class="keyword">` markup the search wraps around matches
+fn strip_highlight(text: &str) -> String {
+    let mut plain = String::with_capacity(text.len());
+    let mut in_tag = false;
+    for c in text.chars() {
+        match c {
+            '<' => in_tag = true,
+            '>' => in_tag = false,
+            c if !in_tag => plain.push(c),
+            _ => {}
+        }
+    }
+
+    plain
+}
+
+impl ApiClient {
+    /// search live streamers whose name matches `keyword`
+    pub async fn search_streamers(&self, keyword: &str) -> ApiResult<Vec<StreamerCandidate>> {
+        let request = self
+            .get_main("/x/web-interface/search/type")
+            .query(&[("search_type", "live_user"), ("keyword", keyword)]);
+        let result: SearchResult = self.send(request).await?;
+
+        Ok(result
+            .result
+            .into_iter()
+            .filter(|candidate| candidate.room_id != 0)
+            .map(|candidate| StreamerCandidate {
+                uname: strip_highlight(&candidate.uname),
+                ..candidate
+            })
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn searches_main_api() {
+        let mut server = mockito::Server::new_async().await;
+        let mock = server
+            .mock("GET", "/x/web-interface/search/type")
+            .match_query(mockito::Matcher::AllOf(vec![
+                mockito::Matcher::UrlEncoded("search_type".into(), "live_user".into()),
+                mockito::Matcher::UrlEncoded("keyword".into(), "星瞳".into()),
+            ]))
+            .with_body(
+                r#"{"code":0,"message":"0","data":{"page":1,"result":[
+                    {"uid":401315430,"uname":"<em class=\"keyword\">星瞳</em>_Official",
+                     "roomid":22886883,"is_live":true,"attentions":1000},
+                    {"uid":2,"uname":"没有直播间","roomid":0,"is_live":false,"attentions":0}
+                ]}}"#,
+            )
+            .create_async()
+            .await;
+
+        let api = ApiClient::builder()
+            .base_url("http://127.0.0.1:1")
+            .main_base_url(server.url())
+            .build();
+        let candidates = api.search_streamers("星瞳").await.unwrap();
+
+        mock.assert_async().await;
+        assert_eq!(candidates.len(), 1);
+        assert_eq!(candidates[0].uname, "星瞳_Official");
+        assert_eq!(candidates[0].room_id, 22886883);
+    }
+
+    #[tokio::test]
+    async fn tolerates_missing_result() {
+        let mut server = mockito::Server::new_async().await;
+        server
+            .mock("GET", "/x/web-interface/search/type")
+            .match_query(mockito::Matcher::Any)
+            .with_body(r#"{"code":0,"message":"0","data":{"page":1}}"#)
+            .create_async()
+            .await;
+
+        let api = ApiClient::builder().main_base_url(server.url()).build();
+        assert!(api.search_streamers("nobody").await.unwrap().is_empty());
+    }
+}
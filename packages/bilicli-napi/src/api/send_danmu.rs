@@ -3,85 +3,238 @@
 use std::collections::HashMap;
 
 use chrono::{Local, Timelike};
-use reqwest::header::{HeaderMap, HeaderValue, COOKIE};
 use serde::Deserialize;
 
-#[derive(Deserialize)]
+use super::{ApiClient, ApiResult};
+
+#[derive(Deserialize, Debug)]
 pub struct BaseUserInfo {
     pub(crate) name: String,
     pub(crate) face: String,
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, Debug)]
 pub struct User {
     pub(crate) uid: u64,
     pub(crate) base: BaseUserInfo,
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, Debug)]
 pub struct ModeInfo {
     mode: i32,
     pub(crate) user: User,
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, Debug)]
 pub struct SendDanmuData {
     pub(crate) mode_info: ModeInfo,
 }
 
-#[derive(Deserialize)]
-pub struct SendDanmuResponse {
-    code: i32,
-    message: String,
-    data: Option<SendDanmuData>,
+/// where a danmaku is shown on the player
+#[derive(Default, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum DanmuMode {
+    #[default]
+    Scroll,
+    Bottom,
+    Top,
 }
 
-pub async fn send_danmu(
-    room_id: u32,
-    content: &str,
-    cookie: String,
-) -> Result<SendDanmuData, String> {
-    let mut headers = HeaderMap::new();
-    headers.insert(COOKIE, HeaderValue::from_str(&cookie).unwrap());
-
-    let kv: Vec<&str> = cookie.split("; ").collect::<Vec<&str>>();
-    let cookie = if let Some(cookie) = kv.iter().find(|s| s.starts_with("bili_jct=")) {
-        if let Some(cookie) = cookie.split("=").nth(1) {
-            cookie.to_string()
-        } else {
-            return Err("无法找到 csrf token".to_string());
+impl DanmuMode {
+    pub fn code(self) -> u32 {
+        match self {
+            DanmuMode::Scroll => 1,
+            DanmuMode::Bottom => 4,
+            DanmuMode::Top => 5,
+        }
+    }
+
+    pub fn from_code(code: u32) -> Option<Self> {
+        match code {
+            1 => Some(DanmuMode::Scroll),
+            4 => Some(DanmuMode::Bottom),
+            5 => Some(DanmuMode::Top),
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            DanmuMode::Scroll => "滚动",
+            DanmuMode::Bottom => "底部",
+            DanmuMode::Top => "顶部",
         }
-    } else {
-        return Err("无法找到 csrf token".to_string());
-    };
-
-    let mut params = HashMap::new();
-    params.insert("csrf", cookie.to_string());
-    params.insert("csrf_token", cookie.to_string());
-    params.insert("color", "16777215".to_string());
-    params.insert("fontsize", "25".to_string());
-    params.insert("mode", "1".to_string());
-    params.insert("msg", content.to_string());
-    params.insert("rnd", Local::now().timestamp().to_string());
-    params.insert("roomid", room_id.to_string());
-
-    let client = reqwest::Client::new();
-
-    let response = client
-        .post("https://api.live.bilibili.com/msg/send")
-        .headers(headers)
-        .form(&params)
-        .send()
-        .await
-        .unwrap();
-
-    if response.status().is_success() {
-        let body = response.json::<SendDanmuResponse>().await.unwrap();
-        if body.code != 0 {
-            return Err(body.message);
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct SendDanmuOptions {
+    /// the color as `0xRRGGBB`
+    pub color: u32,
+    pub mode: DanmuMode,
+    pub fontsize: u32,
+    /// the uid of the viewer this danmaku replies to, shown as an @mention
+    pub reply_mid: Option<u64>,
+}
+
+impl Default for SendDanmuOptions {
+    fn default() -> Self {
+        Self {
+            color: 0xFFFFFF,
+            mode: DanmuMode::Scroll,
+            fontsize: 25,
+            reply_mid: None,
         }
-        Ok(body.data.unwrap())
-    } else {
-        Err(response.text().await.unwrap())
+    }
+}
+
+impl ApiClient {
+    pub async fn send_danmu(
+        &self,
+        room_id: u32,
+        content: &str,
+        options: &SendDanmuOptions,
+    ) -> ApiResult<SendDanmuData> {
+        self.send_msg(room_id, content, None, options).await
+    }
+
+    /// send the room emoticon `emoticon_unique` as emoticon danmaku
+    pub async fn send_emoticon(
+        &self,
+        room_id: u32,
+        emoticon_unique: &str,
+        options: &SendDanmuOptions,
+    ) -> ApiResult<SendDanmuData> {
+        self.send_msg(room_id, emoticon_unique, Some(1), options)
+            .await
+    }
+
+    async fn send_msg(
+        &self,
+        room_id: u32,
+        msg: &str,
+        dm_type: Option<u32>,
+        options: &SendDanmuOptions,
+    ) -> ApiResult<SendDanmuData> {
+        let csrf = self.require_csrf()?;
+
+        let mut params = HashMap::new();
+        params.insert("csrf", csrf.to_string());
+        params.insert("csrf_token", csrf.to_string());
+        params.insert("color", options.color.to_string());
+        params.insert("fontsize", options.fontsize.to_string());
+        params.insert("mode", options.mode.code().to_string());
+        params.insert("msg", msg.to_string());
+        if let Some(reply_mid) = options.reply_mid {
+            params.insert("reply_mid", reply_mid.to_string());
+            params.insert("reply_attr", "0".to_string());
+        }
+        if let Some(dm_type) = dm_type {
+            params.insert("dm_type", dm_type.to_string());
+            params.insert("emoticonOptions", "[object Object]".to_string());
+        }
+        params.insert("rnd", Local::now().timestamp().to_string());
+        params.insert("roomid", room_id.to_string());
+
+        self.send(self.post("/msg/send").form(&params)).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use mockito::Matcher;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn posts_form_with_csrf() {
+        let mut server = mockito::Server::new_async().await;
+        let mock = server
+            .mock("POST", "/msg/send")
+            .match_header("cookie", "SESSDATA=abc; bili_jct=def")
+            .match_body(Matcher::AllOf(vec![
+                Matcher::UrlEncoded("csrf".into(), "def".into()),
+                Matcher::UrlEncoded("roomid".into(), "21452505".into()),
+                Matcher::UrlEncoded("msg".into(), "晚上好".into()),
+                Matcher::UrlEncoded("color".into(), "16777215".into()),
+                Matcher::UrlEncoded("mode".into(), "1".into()),
+            ]))
+            .with_body(
+                r#"{"code":0,"message":"","data":{"mode_info":{"mode":0,"user":{"uid":10001,"base":{"name":"me","face":""}}}}}"#,
+            )
+            .expect(2)
+            .create_async()
+            .await;
+
+        let api = ApiClient::builder()
+            .base_url(server.url())
+            .cookie(Some("SESSDATA=abc; bili_jct=def".to_string()))
+            .build();
+        for _ in 0..2 {
+            let data = api
+                .send_danmu(21452505, "晚上好", &SendDanmuOptions::default())
+                .await
+                .unwrap();
+            assert_eq!(data.mode_info.user.uid, 10001);
+        }
+
+        mock.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn posts_color_and_mode() {
+        let mut server = mockito::Server::new_async().await;
+        let mock = server
+            .mock("POST", "/msg/send")
+            .match_body(Matcher::AllOf(vec![
+                Matcher::UrlEncoded("color".into(), "5948665".into()),
+                Matcher::UrlEncoded("mode".into(), "5".into()),
+                Matcher::UrlEncoded("fontsize".into(), "25".into()),
+                Matcher::UrlEncoded("reply_mid".into(), "10086".into()),
+            ]))
+            .with_body(
+                r#"{"code":0,"message":"","data":{"mode_info":{"mode":0,"user":{"uid":10001,"base":{"name":"me","face":""}}}}}"#,
+            )
+            .create_async()
+            .await;
+
+        let api = ApiClient::builder()
+            .base_url(server.url())
+            .cookie(Some("bili_jct=def".to_string()))
+            .build();
+        let options = SendDanmuOptions {
+            color: 0x5AC4F9,
+            mode: DanmuMode::Top,
+            reply_mid: Some(10086),
+            ..Default::default()
+        };
+        api.send_danmu(1, "顶部弹幕", &options).await.unwrap();
+
+        mock.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn posts_emoticon_unique_id() {
+        let mut server = mockito::Server::new_async().await;
+        let mock = server
+            .mock("POST", "/msg/send")
+            .match_body(Matcher::AllOf(vec![
+                Matcher::UrlEncoded("msg".into(), "official_109".into()),
+                Matcher::UrlEncoded("dm_type".into(), "1".into()),
+            ]))
+            .with_body(
+                r#"{"code":0,"message":"","data":{"mode_info":{"mode":0,"user":{"uid":10001,"base":{"name":"me","face":""}}}}}"#,
+            )
+            .create_async()
+            .await;
+
+        let api = ApiClient::builder()
+            .base_url(server.url())
+            .cookie(Some("bili_jct=def".to_string()))
+            .build();
+        api.send_emoticon(1, "official_109", &SendDanmuOptions::default())
+            .await
+            .unwrap();
+
+        mock.assert_async().await;
     }
 }
@@ -0,0 +1,55 @@
+use std::collections::HashMap;
+
+use serde::de::IgnoredAny;
+
+use super::{ApiClient, ApiResult};
+
+impl ApiClient {
+    /// change the title and the sub-area of `room_id`, only allowed for its streamer
+    pub async fn update_room(&self, room_id: u32, title: &str, area_id: u32) -> ApiResult<()> {
+        let csrf = self.require_csrf()?;
+
+        let mut params = HashMap::new();
+        params.insert("room_id", room_id.to_string());
+        params.insert("title", title.to_string());
+        params.insert("area_id", area_id.to_string());
+        params.insert("csrf", csrf.to_string());
+        params.insert("csrf_token", csrf.to_string());
+
+        let _: IgnoredAny = self
+            .send(self.post("/room/v1/Room/update").form(&params))
+            .await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use mockito::Matcher;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn updates_title_and_area() {
+        let mut server = mockito::Server::new_async().await;
+        let mock = server
+            .mock("POST", "/room/v1/Room/update")
+            .match_body(Matcher::AllOf(vec![
+                Matcher::UrlEncoded("room_id".into(), "21452505".into()),
+                Matcher::UrlEncoded("title".into(), "新标题".into()),
+                Matcher::UrlEncoded("area_id".into(), "86".into()),
+                Matcher::UrlEncoded("csrf".into(), "def".into()),
+            ]))
+            .with_body(r#"{"code":0,"msg":"ok","message":"ok","data":[]}"#)
+            .create_async()
+            .await;
+
+        let api = ApiClient::builder()
+            .base_url(server.url())
+            .cookie(Some("SESSDATA=abc; bili_jct=def".to_string()))
+            .build();
+        api.update_room(21452505, "新标题", 86).await.unwrap();
+
+        mock.assert_async().await;
+    }
+}
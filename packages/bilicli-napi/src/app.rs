@@ -1,28 +1,90 @@
 #![allow(clippy::new_without_default)]
 
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 use crate::{
-    api::send_danmu,
+    api::{
+        ApiResult, DanmuConfig, DanmuMode, GuardMember, MuteDuration, OnlineRank, RoomEmoticon,
+        SendDanmuData, SendDanmuOptions, SilentUser, SilentUserPage, WearedV2,
+    },
+    send_queue::{danmu_length, split_danmu, DanmuContent, QueuedDanmu, SendQueue, SendStatus},
     ui::{
-        footer::Footer, header::Header, helper::centered_rect, tabs::Tabs, AppState, InputMode,
-        SliderBarState,
+        footer::Footer,
+        guards::GuardRoster,
+        header::Header,
+        helper::centered_rect,
+        moderation::{ModerationTarget, MuteMenu, MutedUsersPanel},
+        profile::ProfilePopup,
+        room_editor::RoomEditor,
+        tabs::{Tab, Tabs},
+        Anchor, AppState, Badge, DanmuMsg, Emoticon, Identity, InputMode, MsgType, RoomInfoState,
+        SliderBarState, User,
     },
     TuiState,
 };
-use crossterm::event::{Event, EventStream, KeyCode, KeyEventKind, MouseEventKind};
+use chrono::Local;
+use crossterm::event::{Event, EventStream, KeyCode, KeyEventKind, KeyModifiers, MouseEventKind};
 use futures::StreamExt;
 use ratatui::{
     prelude::*,
     style::palette::tailwind,
-    widgets::{block::Title, Block, Borders, List, ListItem, Padding, Paragraph, Wrap},
+    widgets::{
+        block::Title, Block, Borders, Clear, List, ListItem, ListState, Padding, Paragraph, Wrap,
+    },
 };
+use tokio::task::JoinHandle;
 use tui_textarea::TextArea;
 
+/// the input limit until the account's own limit is known
 pub const MAX_INPUT_LENGTH: usize = 40;
 
+/// how many danmaku a long message may be split into
+pub const MAX_SPLIT_PARTS: usize = 5;
+
 pub type Result<T> = std::result::Result<T, Box<dyn std::error::Error>>;
 
+/// a moderation call waiting to run on the next frame
+#[derive(Debug, Clone)]
+pub enum ModerationRequest {
+    Mute(ModerationTarget, MuteDuration),
+    Unmute(SilentUser),
+    ListMuted,
+}
+
+/// a room editor call waiting to run on the next frame
+#[derive(Debug, Clone)]
+pub enum RoomEditRequest {
+    LoadAreas,
+    Update { title: String, area_id: u32 },
+}
+
+/// a short message shown for a few seconds, e.g. the result of a moderation action
+#[derive(Debug, Clone)]
+pub struct Notice {
+    text: String,
+    error: bool,
+    shown_at: Instant,
+}
+
+impl Notice {
+    const DURATION: Duration = Duration::from_secs(3);
+
+    fn new(text: String, error: bool) -> Self {
+        Self {
+            text,
+            error,
+            shown_at: Instant::now(),
+        }
+    }
+}
+
+/// the viewer the next danmaku replies to
+#[derive(Debug, Clone)]
+pub struct ReplyTarget {
+    pub uid: u64,
+    pub uname: String,
+}
+
 #[derive(Debug, Default)]
 pub struct App {
     pub input_mode: InputMode,
@@ -30,8 +92,44 @@ pub struct App {
     footer: Footer,
     tabs: Tabs,
     pub textarea: TextArea<'static>,
-    pub will_send_message: Vec<String>,
+    pub send_queue: SendQueue,
+    /// the danmaku being sent right now
+    sending: Option<(QueuedDanmu, JoinHandle<ApiResult<SendDanmuData>>)>,
+    /// the guard roster being fetched, which may take many pages
+    guards_loading: Option<JoinHandle<ApiResult<Vec<GuardMember>>>>,
+    /// the online rank being fetched
+    online_rank_loading: Option<JoinHandle<ApiResult<OnlineRank>>>,
+    /// the muted viewers being fetched, which may take many pages
+    muted_users_loading: Option<JoinHandle<ApiResult<SilentUserPage>>>,
+    queue_list: ListState,
     err_text: Option<String>,
+    startup_error_dismissed: bool,
+    streamer_picker: ListState,
+    send_options: SendDanmuOptions,
+    /// the colors and modes the account may use, fetched when first starting to type
+    danmu_config: Option<DanmuConfig>,
+    danmu_config_fetched: bool,
+    emoticon_picker: ListState,
+    emoticons_requested: bool,
+    /// the danmaku length allowed for the account's level, as reported by the server
+    account_input_limit: Option<usize>,
+    /// a limit set by the user that takes precedence over the account's
+    pub input_limit_override: Option<usize>,
+    /// split messages longer than the limit into several danmaku instead of refusing them
+    pub split_long_danmu: bool,
+    reply_to: Option<ReplyTarget>,
+    mute_menu: MuteMenu,
+    muted_users: MutedUsersPanel,
+    moderation_requested: Option<ModerationRequest>,
+    notice: Option<Notice>,
+    room_editor: RoomEditor,
+    room_edit_requested: Option<RoomEditRequest>,
+    guard_roster: GuardRoster,
+    profile: ProfilePopup,
+    /// the uid whose user card is fetched on the next frame
+    profile_requested: Option<u64>,
+    /// the medal we wear in this room, shown on the echo of our danmaku
+    weared_v2: Option<WearedV2>,
 }
 
 unsafe impl Send for App {}
@@ -48,8 +146,46 @@ impl App {
             tokio::time::interval(Duration::from_secs_f32(1.0 / Self::FRAMES_PER_SECOND));
         let mut events = EventStream::new();
 
-        if !self.will_send_message.is_empty() {
-            self.send_danmu(state).await.unwrap_or_default();
+        if self.input_mode == InputMode::Editing && !self.danmu_config_fetched {
+            self.danmu_config_fetched = true;
+            self.fetch_danmu_config(state).await;
+        }
+
+        if self.emoticons_requested {
+            self.emoticons_requested = false;
+            self.fetch_emoticons(state).await;
+        }
+
+        self.poll_send_queue(state).await;
+
+        if let Some(request) = self.moderation_requested.take() {
+            self.moderate(request, state).await;
+        }
+        self.poll_muted_users().await;
+
+        if let Some(request) = self.room_edit_requested.take() {
+            self.edit_room(request, state).await;
+        }
+
+        if let Some(uid) = self.profile_requested.take() {
+            let api = state.api.clone();
+            let result = tokio::spawn(async move { api.get_user_card(uid).await })
+                .await
+                .unwrap();
+
+            // the popup may have moved on to another viewer meanwhile
+            if self.profile.uid() == Some(uid) {
+                self.profile.card = Some(result.map_err(|err| err.to_string()));
+            }
+        }
+
+        self.poll_guards(state).await;
+
+        self.poll_online_rank(state).await;
+
+        if state.room_info_requested {
+            state.room_info_requested = false;
+            state.refresh_room_info().await;
         }
 
         tokio::select! {
@@ -66,63 +202,505 @@ impl App {
         Ok(())
     }
 
-    pub async fn send_danmu(&mut self, state: &mut TuiState) -> Result<()> {
-        self.err_text = None;
-        let content = self.will_send_message.remove(0);
+    /// collect the result of the danmaku in flight and start sending the next one
+    pub async fn poll_send_queue(&mut self, state: &mut TuiState) {
+        if let Some((_, handle)) = &self.sending {
+            if !handle.is_finished() {
+                return;
+            }
+        }
+
+        if let Some((item, handle)) = self.sending.take() {
+            let result = handle.await.unwrap();
+
+            let result = match result {
+                Ok(data) => {
+                    if self.weared_v2.is_none() {
+                        let room_id = state.room_id;
+                        let api = state.api.clone();
+                        let user = tokio::spawn(async move { api.get_room_by_user(room_id).await })
+                            .await
+                            .unwrap();
+                        if let Ok(user) = user {
+                            self.weared_v2 = Some(user.medal.curr_weared_v2);
+                        }
+                    }
+
+                    let msg = self.echo(&item, data, state.streamer_uid);
+                    state.push_echo(msg);
+                    Ok(())
+                }
+                Err(err) => Err(err),
+            };
+
+            self.send_queue.complete(item.id, result, Instant::now());
+        }
+
+        if let Some(item) = self.send_queue.next_ready(Instant::now()) {
+            let room_id = state.room_id;
+            let api = state.api.clone();
+            let content = item.content.clone();
+            let options = item.options;
+            state.start_echo();
+            let handle = tokio::spawn(async move {
+                match &content {
+                    DanmuContent::Text(text) => api.send_danmu(room_id, text, &options).await,
+                    DanmuContent::Emoticon { unique, .. } => {
+                        api.send_emoticon(room_id, unique, &options).await
+                    }
+                }
+            });
+            self.sending = Some((item, handle));
+        }
+    }
+
+    /// collect the fetched online rank and fetch it again when due, only while it is shown
+    async fn poll_online_rank(&mut self, state: &mut TuiState) {
+        if let Some(handle) = &self.online_rank_loading {
+            if !handle.is_finished() {
+                return;
+            }
+        }
+
+        if let Some(handle) = self.online_rank_loading.take() {
+            state.apply_online_rank(handle.await.unwrap());
+        }
+
+        if matches!(self.tabs.selected(), Some(Tab::RankTab(..))) && state.online_rank_due() {
+            state.start_online_rank_refresh();
+            let (ruid, room_id) = (state.streamer_uid, state.room_id);
+            let api = state.api.clone();
+            self.online_rank_loading = Some(tokio::spawn(async move {
+                api.get_online_rank(ruid, room_id).await
+            }));
+        }
+    }
+
+    /// collect the fetched guard roster and start fetching it again when due, without
+    /// holding up the frame while the pages load
+    async fn poll_guards(&mut self, state: &mut TuiState) {
+        if let Some(handle) = &self.guards_loading {
+            if !handle.is_finished() {
+                return;
+            }
+        }
+
+        if let Some(handle) = self.guards_loading.take() {
+            if let Err(err) = state.apply_guards(handle.await.unwrap()) {
+                self.notice = Some(Notice::new(format!("获取大航海列表失败: {}", err), true));
+            }
+        }
+
+        if state.guards_due() {
+            state.start_guards_refresh();
+            let (room_id, ruid) = (state.room_id, state.streamer_uid);
+            let api = state.api.clone();
+            self.guards_loading = Some(tokio::spawn(
+                async move { api.get_guards(room_id, ruid).await },
+            ));
+        }
+    }
+
+    /// the danmaku we just sent as it will come back from the live stream
+    fn echo(&self, item: &QueuedDanmu, data: SendDanmuData, streamer_uid: u64) -> DanmuMsg {
+        let sender = data.mode_info.user;
+        let weared_v2 = self
+            .weared_v2
+            .as_ref()
+            .filter(|medal| !medal.name.is_empty() && medal.level > 0);
+
+        let user = User {
+            uid: sender.uid,
+            uname: sender.base.name,
+            face: Some(sender.base.face),
+            badge: weared_v2.map(|medal| {
+                // the colors may carry an alpha channel, e.g. `#DC6B6B99`
+                let color = match medal.v2_medal_color_start.get(..7) {
+                    Some(color) => color.to_string(),
+                    None => medal.v2_medal_color_text.clone(),
+                };
+                Badge::new(
+                    medal.name.clone(),
+                    medal.level as u8,
+                    color,
+                    None,
+                    Some(Anchor {
+                        uid: medal.target_id,
+                        uname: String::new(),
+                        room_id: 0,
+                        is_same_room: Some(medal.target_id == streamer_uid),
+                    }),
+                    None,
+                )
+            }),
+            identity: Some(Identity {
+                rank: 0,
+                guard_level: weared_v2.map_or(0, |medal| medal.guard_level as u8),
+                room_admin: false,
+            }),
+        };
+
+        let emoticon = match &item.content {
+            DanmuContent::Text(_) => None,
+            DanmuContent::Emoticon { unique, .. } => Some(Emoticon {
+                id: unique.clone(),
+                height: 0,
+                width: 0,
+                url: String::new(),
+            }),
+        };
+
+        DanmuMsg::new(
+            user,
+            item.content.text().to_string(),
+            Local::now().timestamp_millis(),
+            false,
+            emoticon,
+        )
+    }
+
+    async fn moderate(&mut self, request: ModerationRequest, state: &mut TuiState) {
         let room_id = state.room_id;
-        let cookie = state.cookie.clone();
-        let will_send_message = content.clone();
-        let result = tokio::spawn(async move {
-            if let Some(cookie) = cookie {
-                send_danmu(room_id, will_send_message.as_str(), cookie).await
-            } else {
-                Err("未登录".to_string())
+        let api = state.api.clone();
+
+        match request {
+            ModerationRequest::Mute(target, duration) => {
+                let uid = target.uid;
+                let msg = target.msg.clone();
+                let result = tokio::spawn(async move {
+                    api.add_silent_user(room_id, uid, duration, Some(&msg))
+                        .await
+                })
+                .await
+                .unwrap();
+
+                self.notice = Some(match result {
+                    Ok(()) => Notice::new(
+                        format!("已禁言 {} ({})", target.uname, duration.name()),
+                        false,
+                    ),
+                    Err(err) => Notice::new(format!("禁言 {} 失败: {}", target.uname, err), true),
+                });
+            }
+            ModerationRequest::Unmute(user) => {
+                let id = user.id;
+                let result = tokio::spawn(async move { api.remove_silent_user(room_id, id).await })
+                    .await
+                    .unwrap();
+
+                match result {
+                    Ok(()) => {
+                        self.notice =
+                            Some(Notice::new(format!("已解除 {} 的禁言", user.tname), false));
+                        self.moderation_requested = Some(ModerationRequest::ListMuted);
+                    }
+                    Err(err) => {
+                        self.notice = Some(Notice::new(
+                            format!("解除 {} 的禁言失败: {}", user.tname, err),
+                            true,
+                        ));
+                    }
+                }
+            }
+            ModerationRequest::ListMuted => {
+                self.muted_users_loading = Some(tokio::spawn(async move {
+                    api.get_all_silent_users(room_id).await
+                }));
+            }
+        }
+    }
+
+    /// collect the muted viewers once every page has been fetched
+    async fn poll_muted_users(&mut self) {
+        match &self.muted_users_loading {
+            Some(handle) if handle.is_finished() => {}
+            _ => return,
+        }
+
+        if let Some(handle) = self.muted_users_loading.take() {
+            match handle.await.unwrap() {
+                Ok(page) => {
+                    self.muted_users.total = page.total;
+                    self.muted_users.users = Some(page.data);
+                }
+                Err(err) => {
+                    self.muted_users.total = 0;
+                    self.muted_users.users = Some(Vec::new());
+                    self.notice = Some(Notice::new(format!("获取禁言列表失败: {}", err), true));
+                }
+            }
+        }
+    }
+
+    async fn edit_room(&mut self, request: RoomEditRequest, state: &mut TuiState) {
+        let api = state.api.clone();
+
+        match request {
+            RoomEditRequest::LoadAreas => {
+                let result = tokio::spawn(async move { api.get_area_list().await })
+                    .await
+                    .unwrap();
+
+                match result {
+                    Ok(areas) => self.room_editor.set_areas(areas, state),
+                    Err(err) => {
+                        self.notice = Some(Notice::new(format!("获取分区列表失败: {}", err), true));
+                        self.input_mode = InputMode::Normal;
+                    }
+                }
+            }
+            RoomEditRequest::Update { title, area_id } => {
+                let room_id = state.room_id;
+                let result =
+                    tokio::spawn(async move { api.update_room(room_id, &title, area_id).await })
+                        .await
+                        .unwrap();
+
+                match result {
+                    Ok(()) => {
+                        self.notice = Some(Notice::new("直播间信息已更新".to_string(), false));
+                        state.room_info_requested = true;
+                    }
+                    Err(err) => {
+                        self.notice =
+                            Some(Notice::new(format!("更新直播间信息失败: {}", err), true));
+                    }
+                }
             }
+        }
+    }
+
+    async fn fetch_danmu_config(&mut self, state: &mut TuiState) {
+        let room_id = state.room_id;
+        let api = state.api.clone();
+        let (config, user) = tokio::spawn(async move {
+            tokio::join!(api.get_danmu_config(room_id), api.get_room_by_user(room_id))
         })
         .await
         .unwrap();
 
-        // if self.weared_v2.is_none() {
-        //     let result = get_room_by_user(room_id).await;
-        //     if result.is_ok() {
-        //         self.weared_v2 = Some(result.unwrap().medal.curr_weared_v2);
-        //     }
-        // }
+        if let Ok(user) = user {
+            self.send_options = user.send_options();
+            self.weared_v2 = Some(user.medal.curr_weared_v2);
+            let length = user.property.danmu.length as usize;
+            self.account_input_limit = (length > 0).then_some(length);
+        }
+        self.danmu_config = config.ok();
+
+        // fall back to the first allowed option if the last used one is no longer unlocked
+        if let Some(config) = &self.danmu_config {
+            let colors = config.available_colors();
+            if !colors.is_empty()
+                && !colors
+                    .iter()
+                    .any(|c| c.value() == Some(self.send_options.color))
+            {
+                self.send_options.color = colors[0].value().unwrap_or(0xFFFFFF);
+            }
+            let modes = config.available_modes();
+            if !modes.is_empty()
+                && !modes
+                    .iter()
+                    .any(|m| m.mode == self.send_options.mode.code())
+            {
+                self.send_options.mode = DanmuMode::from_code(modes[0].mode).unwrap_or_default();
+            }
+        }
+    }
+
+    async fn fetch_emoticons(&mut self, state: &mut TuiState) {
+        let room_id = state.room_id;
+        let api = state.api.clone();
+        let result = tokio::spawn(async move { api.get_emoticons(room_id).await })
+            .await
+            .unwrap();
+
+        match result {
+            Ok(packages) => {
+                state.set_room_emoticons(room_id, packages);
+                // an earlier failed fetch is no longer relevant
+                self.err_text = None;
+            }
+            Err(err) => {
+                self.err_text = Some(err.to_string());
+                self.input_mode = InputMode::Editing;
+            }
+        }
+    }
+
+    fn open_emoticon_picker(&mut self, state: &TuiState) {
+        self.err_text = None;
+        self.input_mode = InputMode::PickingEmoticon;
+        if !state.room_emoticons.contains_key(&state.room_id) {
+            self.emoticons_requested = true;
+        }
+    }
+
+    pub fn input_limit(&self) -> usize {
+        self.input_limit_override
+            .or(self.account_input_limit)
+            .unwrap_or(MAX_INPUT_LENGTH)
+    }
 
-        if result.is_err() {
-            self.err_text = Some(result.err().unwrap());
+    /// the longest input accepted, several danmaku long when splitting is on
+    fn max_input_length(&self) -> usize {
+        if self.split_long_danmu {
+            self.input_limit() * MAX_SPLIT_PARTS
         } else {
-            // let timestamp = Local::now().timestamp_millis();
-            // let data = result.unwrap();
-            // let mut user: User = User {
-            //     uid: data.mode_info.user.uid,
-            //     uname: data.mode_info.user.base.name,
-            //     face: None,
-            //     badge: None,
-            //     identity: None,
-            // };
-            // if let Some(weared_v2) = &self.weared_v2 {
-            //     user.badge = Some(Badge::new(
-            //         weared_v2.name.clone(),
-            //         weared_v2.level as u8,
-            //         weared_v2.v2_medal_color_text.clone(),
-            //         None,
-            //         None,
-            //         None,
-            //     ));
-            // }
-            // let msg = DanmuMsg::new(user, content, timestamp, false, None);
-            // state
-            //     .messages
-            //     .push((MsgType::Danmu, serde_json::to_string(&msg).unwrap()));
+            self.input_limit()
         }
+    }
 
-        Ok(())
+    fn truncate_input(&mut self) {
+        while danmu_length(&self.textarea.lines()[0]) > self.max_input_length() {
+            if !self.textarea.delete_char() {
+                break;
+            }
+        }
+    }
+
+    /// the input as the danmaku to send, in order
+    fn input_parts(&self) -> Vec<String> {
+        let text = &self.textarea.lines()[0];
+        if self.split_long_danmu {
+            split_danmu(text, self.input_limit())
+        } else {
+            vec![text.to_string()]
+        }
+    }
+
+    fn current_tab(&self) -> Option<&Tab> {
+        self.tabs.tabs.get(self.tabs.state.selected()?)
+    }
+
+    /// the indices into `state.messages` of the danmaku shown in the current tab
+    fn replyable_messages(&self, state: &TuiState) -> Vec<usize> {
+        let Some(tab) = self.current_tab() else {
+            return Vec::new();
+        };
+
+        state
+            .messages
+            .iter()
+            .enumerate()
+            .filter(|(_, (t, _))| *t == MsgType::Danmu && tab.accepts(*t))
+            .map(|(index, _)| index)
+            .collect()
+    }
+
+    /// select the latest danmaku of the current tab
+    fn start_selecting(&mut self, state: &mut TuiState) {
+        if let Some(index) = self.replyable_messages(state).last() {
+            state.selected_message = Some(*index);
+            self.input_mode = InputMode::SelectingMessage;
+        }
+    }
+
+    fn move_selection(&mut self, state: &mut TuiState, forward: bool) {
+        let messages = self.replyable_messages(state);
+        let Some(selected) = state.selected_message else {
+            return;
+        };
+
+        let next = if forward {
+            messages.iter().find(|index| **index > selected)
+        } else {
+            messages.iter().rev().find(|index| **index < selected)
+        };
+        if let Some(next) = next {
+            state.selected_message = Some(*next);
+        }
+    }
+
+    /// open the input box with the sender of the selected danmaku as reply target
+    fn reply_to_selected(&mut self, state: &mut TuiState) {
+        let Some(index) = state.selected_message.take() else {
+            return;
+        };
+        if state.cookie.is_none() {
+            self.input_mode = InputMode::Normal;
+            return;
+        }
+
+        if let Some(msg) = parse_danmu(state, index) {
+            self.reply_to = Some(ReplyTarget {
+                uid: msg.user().uid,
+                uname: msg.user().uname.clone(),
+            });
+        }
+
+        self.input_mode = InputMode::Editing;
+        self.textarea
+            .set_style(Style::default().fg(Color::LightGreen));
+    }
+
+    fn selected_danmu(&self, state: &TuiState) -> Option<DanmuMsg> {
+        parse_danmu(state, state.selected_message?)
+    }
+
+    fn selected_queue_item(&self) -> Option<u64> {
+        let index = self.queue_list.selected()?;
+        self.send_queue.items().nth(index).map(|item| item.id)
+    }
+
+    /// switch to the next color the account has unlocked
+    fn next_danmu_color(&mut self) {
+        let Some(config) = &self.danmu_config else {
+            return;
+        };
+
+        let colors: Vec<u32> = config
+            .available_colors()
+            .iter()
+            .filter_map(|color| color.value())
+            .collect();
+        if let Some(color) = next_option(&colors, &self.send_options.color) {
+            self.send_options.color = color;
+        }
+    }
+
+    /// switch to the next position mode the account may use
+    fn next_danmu_mode(&mut self) {
+        let Some(config) = &self.danmu_config else {
+            return;
+        };
+
+        let modes: Vec<DanmuMode> = config
+            .available_modes()
+            .iter()
+            .filter_map(|mode| DanmuMode::from_code(mode.mode))
+            .collect();
+        if let Some(mode) = next_option(&modes, &self.send_options.mode) {
+            self.send_options.mode = mode;
+        }
     }
 
     pub fn handle_events(&mut self, event: &Event, state: &mut TuiState) -> crate::app::Result<()> {
         match event {
             Event::Key(key) => match self.input_mode {
+                InputMode::Normal
+                    if key.kind == KeyEventKind::Press && self.showing_streamer_picker(state) =>
+                {
+                    match key.code {
+                        KeyCode::Up => self.streamer_picker.select_previous(),
+                        KeyCode::Down => self.streamer_picker.select_next(),
+                        KeyCode::Enter => {
+                            state.pick_streamer(self.streamer_picker.selected().unwrap_or(0))
+                        }
+                        KeyCode::Char('q') => state.quit(),
+                        _ => {}
+                    }
+                }
+                InputMode::Normal
+                    if key.kind == KeyEventKind::Press && self.showing_startup_error(state) =>
+                {
+                    match key.code {
+                        KeyCode::Char('r') => state.room_info_requested = true,
+                        KeyCode::Char('c') => self.startup_error_dismissed = true,
+                        KeyCode::Char('q') => state.quit(),
+                        _ => {}
+                    }
+                }
                 InputMode::Normal if key.kind == KeyEventKind::Press => match key.code {
                     KeyCode::Up if state.state == AppState::Running => self.previous_tab(),
                     KeyCode::Down if state.state == AppState::Running => self.next_tab(),
@@ -139,6 +717,37 @@ impl App {
                     KeyCode::Char('t') if state.state == AppState::Running => {
                         self.toggle_slider_bar(state)
                     }
+                    KeyCode::Char('m') if state.state == AppState::Running => {
+                        self.start_selecting(state)
+                    }
+                    KeyCode::Char('u')
+                        if state.state == AppState::Running && state.cookie.is_some() =>
+                    {
+                        self.muted_users.users = None;
+                        self.moderation_requested = Some(ModerationRequest::ListMuted);
+                        self.input_mode = InputMode::ViewingMuted;
+                    }
+                    KeyCode::Char('e') if state.state == AppState::Running && state.is_anchor() => {
+                        self.room_editor.open(state);
+                        if self.room_editor.areas.is_none() {
+                            self.room_edit_requested = Some(RoomEditRequest::LoadAreas);
+                        }
+                        self.input_mode = InputMode::EditingRoom;
+                    }
+                    KeyCode::Char('g')
+                        if state.state == AppState::Running && state.room_resolved =>
+                    {
+                        self.guard_roster.open();
+                        if state.guards.is_none() {
+                            state.guards_requested = true;
+                        }
+                        self.input_mode = InputMode::ViewingGuards;
+                    }
+                    KeyCode::Char('l')
+                        if state.state == AppState::Running && !self.send_queue.is_empty() =>
+                    {
+                        self.input_mode = InputMode::ManagingQueue;
+                    }
                     KeyCode::Enter
                         if state.state == AppState::Running && state.cookie.is_some() =>
                     {
@@ -157,21 +766,179 @@ impl App {
                     }
                     KeyCode::Enter => {
                         if !self.textarea.lines()[0].is_empty() {
-                            self.will_send_message
-                                .push(self.textarea.lines()[0].to_string());
+                            // only the first part carries the @mention
+                            let mut options = SendDanmuOptions {
+                                reply_mid: self.reply_to.take().map(|target| target.uid),
+                                ..self.send_options
+                            };
+                            let parts = self.input_parts().into_iter().map(|part| {
+                                let part_options = options;
+                                options.reply_mid = None;
+                                (DanmuContent::Text(part), part_options)
+                            });
+                            self.send_queue.push_parts(parts);
                             self.input_mode = InputMode::Normal;
                         }
                     }
                     KeyCode::Esc => {
+                        self.reply_to = None;
                         self.input_mode = InputMode::Normal;
                     }
+                    KeyCode::Tab => self.next_danmu_color(),
+                    KeyCode::BackTab => self.next_danmu_mode(),
+                    KeyCode::Char('e') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                        self.open_emoticon_picker(state)
+                    }
+                    KeyCode::Char('t') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                        self.split_long_danmu = !self.split_long_danmu;
+                        self.truncate_input();
+                    }
                     _ => {
-                        if self.textarea.input(*key)
-                            && self.textarea.lines()[0].len() > MAX_INPUT_LENGTH
+                        if self.textarea.input(*key) {
+                            self.truncate_input();
+                        }
+                    }
+                },
+                InputMode::PickingEmoticon if key.kind == KeyEventKind::Press => match key.code {
+                    KeyCode::Up => self.emoticon_picker.select_previous(),
+                    KeyCode::Down => self.emoticon_picker.select_next(),
+                    KeyCode::Enter => {
+                        let index = self.emoticon_picker.selected().unwrap_or(0);
+                        if let Some(emoticon) = available_emoticons(state).get(index) {
+                            let content = DanmuContent::Emoticon {
+                                unique: emoticon.emoticon_unique.clone(),
+                                emoji: emoticon.emoji.clone(),
+                            };
+                            self.send_queue.push(content, self.send_options);
+                            self.input_mode = InputMode::Normal;
+                        }
+                    }
+                    KeyCode::Esc => self.input_mode = InputMode::Editing,
+                    _ => {}
+                },
+                InputMode::SelectingMessage if key.kind == KeyEventKind::Press => match key.code {
+                    KeyCode::Up => self.move_selection(state, false),
+                    KeyCode::Down => self.move_selection(state, true),
+                    KeyCode::Enter | KeyCode::Char('r') => self.reply_to_selected(state),
+                    KeyCode::Char('i') => {
+                        if let Some(msg) = self.selected_danmu(state) {
+                            let uid = msg.user().uid;
+                            self.profile.open(msg.into_user(), count_danmu(state, uid));
+                            self.profile_requested = Some(uid);
+                            self.input_mode = InputMode::ViewingProfile;
+                        }
+                    }
+                    KeyCode::Char('b') if state.cookie.is_some() => {
+                        if let Some(msg) = self.selected_danmu(state) {
+                            self.mute_menu.open(ModerationTarget {
+                                uid: msg.user().uid,
+                                uname: msg.user().uname.clone(),
+                                msg: msg.content().to_string(),
+                            });
+                            self.input_mode = InputMode::Muting;
+                        }
+                    }
+                    KeyCode::Esc => {
+                        state.selected_message = None;
+                        self.input_mode = InputMode::Normal;
+                    }
+                    _ => {}
+                },
+                InputMode::Muting if key.kind == KeyEventKind::Press => match key.code {
+                    KeyCode::Up => self.mute_menu.state.select_previous(),
+                    KeyCode::Down => self.mute_menu.state.select_next(),
+                    KeyCode::Enter => {
+                        if let (Some(target), Some(duration)) =
+                            (self.mute_menu.target.take(), self.mute_menu.selected())
                         {
-                            self.textarea.delete_char();
+                            self.moderation_requested =
+                                Some(ModerationRequest::Mute(target, duration));
+                        }
+                        state.selected_message = None;
+                        self.input_mode = InputMode::Normal;
+                    }
+                    KeyCode::Esc => {
+                        self.mute_menu.target = None;
+                        self.input_mode = InputMode::SelectingMessage;
+                    }
+                    _ => {}
+                },
+                InputMode::ViewingMuted if key.kind == KeyEventKind::Press => match key.code {
+                    KeyCode::Up => self.muted_users.state.select_previous(),
+                    KeyCode::Down => self.muted_users.state.select_next(),
+                    KeyCode::Char('x') | KeyCode::Delete => {
+                        if let Some(user) = self.muted_users.selected() {
+                            self.moderation_requested =
+                                Some(ModerationRequest::Unmute(user.clone()));
+                        }
+                    }
+                    KeyCode::Char('r') => {
+                        self.moderation_requested = Some(ModerationRequest::ListMuted)
+                    }
+                    KeyCode::Esc => self.input_mode = InputMode::Normal,
+                    _ => {}
+                },
+                InputMode::EditingRoom if key.kind == KeyEventKind::Press => match key.code {
+                    KeyCode::Tab => self.room_editor.next_focus(),
+                    KeyCode::Up => self.room_editor.select_previous(),
+                    KeyCode::Down => self.room_editor.select_next(),
+                    KeyCode::Enter => {
+                        let title = self.room_editor.title_text();
+                        if let Some(area) = self.room_editor.selected_area() {
+                            if !title.is_empty() {
+                                self.room_edit_requested = Some(RoomEditRequest::Update {
+                                    title,
+                                    area_id: area.id,
+                                });
+                                self.input_mode = InputMode::Normal;
+                            }
                         }
                     }
+                    KeyCode::Esc => self.input_mode = InputMode::Normal,
+                    _ => self.room_editor.input(*key),
+                },
+                InputMode::ViewingGuards
+                    if key.kind == KeyEventKind::Press && self.guard_roster.searching =>
+                {
+                    match key.code {
+                        KeyCode::Enter | KeyCode::Esc => self.guard_roster.searching = false,
+                        KeyCode::Backspace => self.guard_roster.pop_query(),
+                        KeyCode::Char(c) => self.guard_roster.push_query(c),
+                        _ => {}
+                    }
+                }
+                InputMode::ViewingGuards if key.kind == KeyEventKind::Press => match key.code {
+                    KeyCode::Up => self.guard_roster.state.select_previous(),
+                    KeyCode::Down => self.guard_roster.state.select_next(),
+                    KeyCode::Char('/') => self.guard_roster.searching = true,
+                    KeyCode::Char('r') => state.guards_requested = true,
+                    KeyCode::Esc if !self.guard_roster.query.is_empty() => self.guard_roster.open(),
+                    KeyCode::Esc => self.input_mode = InputMode::Normal,
+                    _ => {}
+                },
+                InputMode::ViewingProfile
+                    if key.kind == KeyEventKind::Press && key.code == KeyCode::Esc =>
+                {
+                    self.input_mode = InputMode::SelectingMessage;
+                }
+                InputMode::ManagingQueue if key.kind == KeyEventKind::Press => match key.code {
+                    KeyCode::Up => self.queue_list.select_previous(),
+                    KeyCode::Down => self.queue_list.select_next(),
+                    KeyCode::Char('x') | KeyCode::Delete => {
+                        if let Some(id) = self.selected_queue_item() {
+                            self.send_queue.cancel(id);
+                        }
+                        if self.send_queue.is_empty() {
+                            self.input_mode = InputMode::Normal;
+                        }
+                    }
+                    KeyCode::Char('r') => {
+                        if let Some(id) = self.selected_queue_item() {
+                            self.send_queue.retry(id);
+                        }
+                    }
+                    KeyCode::Esc => self.input_mode = InputMode::Normal,
+                    _ => {}
                 },
                 _ => {}
             },
@@ -190,6 +957,20 @@ impl App {
         Ok(())
     }
 
+    /// several streamers match the name given on the command line
+    fn showing_streamer_picker(&self, state: &TuiState) -> bool {
+        state.state == AppState::Running
+            && !state.room_resolved
+            && !state.streamer_candidates.is_empty()
+    }
+
+    /// the first room info fetch failed and the user has not chosen to continue yet
+    fn showing_startup_error(&self, state: &TuiState) -> bool {
+        state.state == AppState::Running
+            && !self.startup_error_dismissed
+            && matches!(state.room_info, RoomInfoState::Failed { loaded: false, .. })
+    }
+
     pub fn toggle_slider_bar(&mut self, state: &mut TuiState) {
         state.slider_bar_state = match state.slider_bar_state {
             SliderBarState::Normal => SliderBarState::Hiding,
@@ -245,6 +1026,13 @@ impl StatefulWidget for &mut App {
 
         let [header_area, inner_area, footer_area] = vertical.areas(root);
         let [tabs_area, content_area] = horizontal.areas(inner_area);
+        let queue_height = match self.send_queue.len() {
+            0 => 0,
+            len => len.min(4) as u16 + 2,
+        };
+        let [content_area, queue_area] =
+            Layout::vertical([Constraint::Fill(1), Constraint::Length(queue_height)])
+                .areas(content_area);
 
         self.header.render(header_area, buf, state);
 
@@ -254,10 +1042,40 @@ impl StatefulWidget for &mut App {
 
         self.render_selected_tab(content_area, buf, state);
 
-        if self.input_mode == InputMode::Editing {
-            self.render_input(footer_area, buf);
-        } else {
-            self.footer.render(footer_area, buf, state);
+        if !self.send_queue.is_empty() {
+            self.render_send_queue(queue_area, buf);
+        }
+
+        match self.input_mode {
+            InputMode::Editing => self.render_input(footer_area, buf),
+            InputMode::PickingEmoticon => {
+                self.render_input(footer_area, buf);
+                self.render_emoticon_picker(inner_area, buf, state);
+            }
+            _ => self.footer.render(footer_area, buf, state),
+        }
+
+        match self.input_mode {
+            InputMode::Muting => self.mute_menu.render(root, buf),
+            InputMode::ViewingMuted => self.muted_users.render(root, buf),
+            InputMode::EditingRoom => self.room_editor.render(root, buf),
+            InputMode::ViewingGuards => self.guard_roster.render(root, buf, state),
+            InputMode::ViewingProfile => self.profile.render(root, buf),
+            _ => {}
+        }
+
+        if let Some(notice) = &self.notice {
+            if notice.shown_at.elapsed() < Notice::DURATION {
+                render_notice(notice, content_area, buf);
+            } else {
+                self.notice = None;
+            }
+        }
+
+        if self.showing_streamer_picker(state) {
+            self.render_streamer_picker(root, buf, state);
+        } else if self.showing_startup_error(state) {
+            self.render_startup_error(root, buf, state);
         }
     }
 }
@@ -288,6 +1106,205 @@ impl App {
         .render(area, buf);
     }
 
+    fn render_streamer_picker(&mut self, area: Rect, buf: &mut Buffer, state: &TuiState) {
+        let area = centered_rect(60, 60, area);
+        let block = Block::bordered()
+            .title(format!(
+                " 找到 {} 位主播, 按 Enter 选择 ",
+                state.streamer_candidates.len()
+            ))
+            .title_alignment(Alignment::Center)
+            .border_set(symbols::border::ROUNDED)
+            .border_style(Style::default().fg(tailwind::ORANGE.c400))
+            .padding(Padding::uniform(1));
+
+        let items: Vec<ListItem> = state
+            .streamer_candidates
+            .iter()
+            .map(|candidate| {
+                let mut spans = vec![
+                    Span::from(candidate.uname.clone()).bold(),
+                    Span::raw(" "),
+                    Span::from(format!("UID {} 房间 {}", candidate.uid, candidate.room_id))
+                        .fg(Color::DarkGray),
+                ];
+                if candidate.is_live {
+                    spans.push(Span::raw(" "));
+                    spans.push(Span::from("[直播中]").fg(Color::LightGreen));
+                }
+                ListItem::new(Line::from(spans))
+            })
+            .collect();
+
+        if self.streamer_picker.selected().is_none() {
+            self.streamer_picker.select_first();
+        }
+
+        let list = List::new(items)
+            .block(block)
+            .highlight_style(
+                Style::default()
+                    .bg(tailwind::YELLOW.c300)
+                    .fg(tailwind::BLACK),
+            )
+            .highlight_spacing(ratatui::widgets::HighlightSpacing::WhenSelected);
+
+        Clear.render(area, buf);
+        StatefulWidget::render(list, area, buf, &mut self.streamer_picker);
+    }
+
+    fn render_send_queue(&mut self, area: Rect, buf: &mut Buffer) {
+        let managing = self.input_mode == InputMode::ManagingQueue;
+        let block = Block::bordered()
+            .border_type(ratatui::widgets::BorderType::Rounded)
+            .border_style(if managing {
+                Style::default().fg(tailwind::ORANGE.c400)
+            } else {
+                Style::default()
+            })
+            .title(if managing {
+                " 发送队列 (x 取消, r 重试, Esc 返回) "
+            } else {
+                " 发送队列 (按 l 管理) "
+            })
+            .padding(Padding::horizontal(1));
+
+        let now = Instant::now();
+        let items: Vec<ListItem> = self
+            .send_queue
+            .items()
+            .map(|item| {
+                let status = match &item.status {
+                    SendStatus::Queued if self.send_queue.is_held(item) => {
+                        Span::from("[等待前一部分]").fg(Color::DarkGray)
+                    }
+                    SendStatus::Queued => Span::from("[排队中]").fg(Color::DarkGray),
+                    SendStatus::Sending => Span::from("[发送中]").fg(Color::LightCyan),
+                    SendStatus::Retrying { attempt, retry_at } => Span::from(format!(
+                        "[发送过快, {} 秒后第 {} 次重试]",
+                        retry_at.saturating_duration_since(now).as_secs() + 1,
+                        attempt
+                    ))
+                    .fg(Color::Yellow),
+                    SendStatus::Failed(err) => {
+                        Span::from(format!("[失败: {}]", err)).fg(Color::Red)
+                    }
+                };
+                ListItem::new(Line::from(vec![
+                    status,
+                    Span::raw(" "),
+                    Span::raw(item.content.text().to_string()),
+                ]))
+            })
+            .collect();
+
+        if managing && self.queue_list.selected().is_none() {
+            self.queue_list.select_first();
+        }
+
+        let list = List::new(items)
+            .block(block)
+            .highlight_style(if managing {
+                Style::default()
+                    .bg(tailwind::YELLOW.c300)
+                    .fg(tailwind::BLACK)
+            } else {
+                Style::default()
+            })
+            .highlight_spacing(ratatui::widgets::HighlightSpacing::WhenSelected);
+
+        StatefulWidget::render(list, area, buf, &mut self.queue_list);
+    }
+
+    /// a popup in the bottom left of `area`, right above the input box
+    fn render_emoticon_picker(&mut self, area: Rect, buf: &mut Buffer, state: &TuiState) {
+        let area = Rect {
+            x: area.x,
+            y: area.bottom().saturating_sub(area.height.min(14)),
+            width: area.width.min(50),
+            height: area.height.min(14),
+        };
+        let block = Block::bordered()
+            .title(" 表情 (Enter 发送, Esc 返回) ")
+            .border_set(symbols::border::ROUNDED)
+            .border_style(Style::default().fg(tailwind::ORANGE.c400));
+
+        Clear.render(area, buf);
+
+        if !state.room_emoticons.contains_key(&state.room_id) {
+            Paragraph::new("正在加载表情...")
+                .block(block)
+                .render(area, buf);
+            return;
+        }
+
+        let emoticons = available_emoticons(state);
+        if emoticons.is_empty() {
+            Paragraph::new("没有可用的表情")
+                .block(block)
+                .render(area, buf);
+            return;
+        }
+
+        let items: Vec<ListItem> = emoticons
+            .iter()
+            .map(|emoticon| {
+                ListItem::new(Line::from(vec![
+                    Span::from(emoticon.emoji.clone()).bold(),
+                    Span::raw(" "),
+                    Span::from(emoticon.descript.clone()).fg(Color::DarkGray),
+                ]))
+            })
+            .collect();
+
+        if self.emoticon_picker.selected().is_none() {
+            self.emoticon_picker.select_first();
+        }
+
+        let list = List::new(items)
+            .block(block)
+            .highlight_style(
+                Style::default()
+                    .bg(tailwind::YELLOW.c300)
+                    .fg(tailwind::BLACK),
+            )
+            .highlight_spacing(ratatui::widgets::HighlightSpacing::WhenSelected);
+
+        StatefulWidget::render(list, area, buf, &mut self.emoticon_picker);
+    }
+
+    fn render_startup_error(&self, area: Rect, buf: &mut Buffer, state: &TuiState) {
+        let RoomInfoState::Failed { message, .. } = &state.room_info else {
+            return;
+        };
+
+        let area = centered_rect(60, 40, area);
+        let block = Block::bordered()
+            .title(" 错误 ")
+            .title_alignment(Alignment::Center)
+            .border_set(symbols::border::ROUNDED)
+            .border_style(Style::default().fg(tailwind::RED.c400))
+            .padding(Padding::uniform(1));
+
+        Clear.render(area, buf);
+        Paragraph::new(vec![
+            Line::from(format!("无法获取直播间 {} 的信息", state.room_query).bold()).centered(),
+            Line::from(message.clone().fg(tailwind::RED.c300)).centered(),
+            Line::raw(""),
+            Line::from(vec![
+                Span::from("重试 (按 R/r)").bold().fg(tailwind::ORANGE.c300),
+                Span::raw("    "),
+                Span::from("继续 (按 C/c)").bold().fg(tailwind::GREEN.c300),
+                Span::raw("    "),
+                Span::from("退出 (按 Q/q)").bold().fg(tailwind::RED.c300),
+            ])
+            .centered(),
+        ])
+        .block(block)
+        .wrap(Wrap { trim: true })
+        .render(area, buf);
+    }
+
     fn render_tabs(&mut self, area: Rect, buf: &mut Buffer) {
         let highlight_style = Style::default()
             .bg(tailwind::YELLOW.c300)
@@ -326,7 +1343,7 @@ impl App {
 
     fn render_input(&mut self, area: Rect, buf: &mut Buffer) {
         self.textarea
-            .set_placeholder_text("按 Enter 发送弹幕, Esc 取消输入");
+            .set_placeholder_text("按 Enter 发送弹幕, Ctrl+E 选择表情, Esc 取消输入");
         self.textarea
             .set_style(Style::default().fg(Color::LightCyan));
         let style = {
@@ -348,19 +1365,125 @@ impl App {
                 .borders(Borders::ALL)
                 .padding(Padding::left(1))
                 .title({
+                    let reply = match &self.reply_to {
+                        Some(target) => format!(" 回复 @{} ·", target.uname),
+                        None => String::new(),
+                    };
                     if let Some(err_text) = &self.err_text {
                         Title::from(format!("错误: {} ", err_text).red())
+                    } else if self.split_long_danmu {
+                        Title::from(format!(
+                            "{} {} / {} (自动拆分为 {} 条, Ctrl+T 关闭) ",
+                            reply,
+                            danmu_length(&self.textarea.lines()[0]),
+                            self.input_limit(),
+                            self.input_parts().len()
+                        ))
                     } else {
                         Title::from(format!(
-                            " {} / {} ",
-                            self.textarea.lines()[0].len(),
-                            MAX_INPUT_LENGTH
+                            "{} {} / {} (Ctrl+T 自动拆分) ",
+                            reply,
+                            danmu_length(&self.textarea.lines()[0]),
+                            self.input_limit()
                         ))
                     }
                 })
-                .title_alignment(Alignment::Center),
+                .title_alignment(Alignment::Center)
+                .title_bottom(self.send_options_line()),
         );
 
         self.textarea.render(area, buf);
     }
+
+    fn send_options_line(&self) -> Line<'static> {
+        let color = self.send_options.color;
+        let color_name = self
+            .danmu_config
+            .as_ref()
+            .and_then(|config| {
+                config
+                    .available_colors()
+                    .into_iter()
+                    .find(|c| c.value() == Some(color))
+                    .map(|c| c.name.clone())
+            })
+            .unwrap_or_else(|| format!("#{:06X}", color));
+
+        Line::from(vec![
+            Span::raw(" 颜色 "),
+            Span::from("■ ").fg(Color::Rgb(
+                (color >> 16) as u8,
+                (color >> 8) as u8,
+                color as u8,
+            )),
+            Span::raw(color_name),
+            Span::raw(format!("  模式 {} ", self.send_options.mode.name())),
+            Span::from("(Tab 切换颜色, Shift+Tab 切换模式) ").fg(Color::DarkGray),
+        ])
+        .centered()
+    }
+}
+
+/// a one line box in the top right corner of `area`
+fn render_notice(notice: &Notice, area: Rect, buf: &mut Buffer) {
+    let width = (Line::raw(notice.text.as_str()).width() as u16 + 4).min(area.width);
+    let area = Rect {
+        x: area.right().saturating_sub(width),
+        y: area.y,
+        width,
+        height: area.height.min(3),
+    };
+    let color = if notice.error {
+        tailwind::RED.c400
+    } else {
+        tailwind::GREEN.c400
+    };
+
+    Clear.render(area, buf);
+    Paragraph::new(notice.text.as_str())
+        .block(
+            Block::bordered()
+                .border_set(symbols::border::ROUNDED)
+                .border_style(Style::default().fg(color)),
+        )
+        .centered()
+        .render(area, buf);
+}
+
+/// how many danmaku `uid` sent since we joined, not counting the history
+fn count_danmu(state: &TuiState, uid: u64) -> usize {
+    (0..state.messages.len())
+        .filter_map(|index| parse_danmu(state, index))
+        .filter(|msg| msg.user().uid == uid && !msg.is_history())
+        .count()
+}
+
+fn parse_danmu(state: &TuiState, index: usize) -> Option<DanmuMsg> {
+    let (t, msg) = state.messages.get(index)?;
+    if *t != MsgType::Danmu {
+        return None;
+    }
+
+    serde_json::from_str(msg).ok()
+}
+
+/// the emoticons of the current room the account may send
+fn available_emoticons(state: &TuiState) -> Vec<&RoomEmoticon> {
+    state
+        .room_emoticons
+        .get(&state.room_id)
+        .into_iter()
+        .flatten()
+        .flat_map(|package| &package.emoticons)
+        .filter(|emoticon| emoticon.perm == 1)
+        .collect()
+}
+
+/// the option after `current` in `options`, wrapping around
+fn next_option<T: Copy + PartialEq>(options: &[T], current: &T) -> Option<T> {
+    let index = options.iter().position(|option| option == current);
+    match index {
+        Some(index) => options.get((index + 1) % options.len()).copied(),
+        None => options.first().copied(),
+    }
 }
@@ -0,0 +1,207 @@
+use std::{collections::HashMap, fs, path::Path, time::Duration};
+
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    api::{ApiClient, ApiResult, GiftConfig},
+    paths::cache_dir,
+};
+
+/// how long a downloaded gift catalog is used before it is fetched again
+pub const CACHE_EXPIRY: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// the gifts of a room by id, used to resolve the metadata of gift messages
+#[derive(Debug, Default, Clone)]
+pub struct GiftCatalog {
+    gifts: HashMap<u32, GiftConfig>,
+}
+
+#[derive(Deserialize, Serialize)]
+struct CachedCatalog {
+    /// unix timestamp in seconds
+    fetched_at: i64,
+    gifts: Vec<GiftConfig>,
+}
+
+impl GiftCatalog {
+    pub fn new(gifts: Vec<GiftConfig>) -> Self {
+        Self {
+            gifts: gifts.into_iter().map(|gift| (gift.id, gift)).collect(),
+        }
+    }
+
+    pub fn get(&self, id: u32) -> Option<&GiftConfig> {
+        self.gifts.get(&id)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.gifts.is_empty()
+    }
+
+    /// the catalog of `room_id` from the disk cache, downloading it when missing or expired
+    pub async fn load(api: &ApiClient, room_id: u32) -> ApiResult<Self> {
+        let path = cache_dir().map(|dir| dir.join(format!("gifts-{}.json", room_id)));
+        Self::load_from(api, room_id, path.as_deref()).await
+    }
+
+    async fn load_from(api: &ApiClient, room_id: u32, path: Option<&Path>) -> ApiResult<Self> {
+        let now = Utc::now().timestamp();
+        if let Some(gifts) = path.and_then(|path| read_cache(path, now)) {
+            return Ok(Self::new(gifts));
+        }
+
+        let gifts = api.get_gift_config(room_id).await?;
+        if let Some(path) = path {
+            // the cache only saves a request, failing to write it is not an error
+            let _ = write_cache(path, now, &gifts);
+        }
+
+        Ok(Self::new(gifts))
+    }
+}
+
+fn read_cache(path: &Path, now: i64) -> Option<Vec<GiftConfig>> {
+    let cached: CachedCatalog = serde_json::from_slice(&fs::read(path).ok()?).ok()?;
+    let age = now.checked_sub(cached.fetched_at)?;
+    (0..CACHE_EXPIRY.as_secs() as i64)
+        .contains(&age)
+        .then_some(cached.gifts)
+}
+
+fn write_cache(path: &Path, now: i64, gifts: &[GiftConfig]) -> std::io::Result<()> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+
+    let cached = CachedCatalog {
+        fetched_at: now,
+        gifts: gifts.to_vec(),
+    };
+    fs::write(path, serde_json::to_vec(&cached)?)
+}
+
+/// what a gift message is worth
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GiftValue {
+    /// in gold, 1000 gold are 1 元 or 10 电池
+    Gold(u32),
+    /// in silver melon seeds, which cannot be bought
+    Silver(u32),
+    Free,
+}
+
+impl GiftValue {
+    pub fn new(coin_type: &str, price: u32, amount: u32) -> Self {
+        let total = price.saturating_mul(amount);
+        match coin_type {
+            _ if total == 0 => GiftValue::Free,
+            "silver" => GiftValue::Silver(total),
+            _ => GiftValue::Gold(total),
+        }
+    }
+
+    pub fn text(self) -> Option<String> {
+        match self {
+            GiftValue::Gold(gold) => Some(format!(
+                "{:.1} 元 / {} 电池",
+                gold as f32 / 1000.0,
+                gold / 100
+            )),
+            GiftValue::Silver(silver) => Some(format!("{} 银瓜子", silver)),
+            GiftValue::Free => None,
+        }
+    }
+}
+
+/// a short icon for the common gifts
+pub fn gift_icon(name: &str) -> &'static str {
+    match name {
+        "小心心" => "💗",
+        "辣条" => "🌶",
+        "小花花" => "🌸",
+        "牛哇牛哇" | "牛哇" => "🐮",
+        "打call" => "📣",
+        "干杯" => "🍻",
+        "这个好诶" => "👍",
+        "粉丝团灯牌" => "💡",
+        "人气票" => "🎫",
+        "小电视飞船" => "📺",
+        "flag" => "🚩",
+        _ if name.contains("盲盒") => "🎲",
+        _ => "🎁",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn gift(id: u32, name: &str) -> GiftConfig {
+        GiftConfig {
+            id,
+            name: name.to_string(),
+            price: 100,
+            coin_type: "gold".to_string(),
+            img_basic: String::new(),
+        }
+    }
+
+    #[test]
+    fn reads_only_fresh_cache() {
+        let path = std::env::temp_dir()
+            .join(format!("bilicli-test-{}", std::process::id()))
+            .join("gifts.json");
+        write_cache(&path, 1000, &[gift(1, "小花花")]).unwrap();
+
+        assert_eq!(read_cache(&path, 1000).unwrap()[0].name, "小花花");
+        assert!(read_cache(&path, 1000 + CACHE_EXPIRY.as_secs() as i64).is_none());
+        assert!(read_cache(&path, 999).is_none());
+
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+
+    #[tokio::test]
+    async fn downloads_when_cache_is_missing() {
+        let mut server = mockito::Server::new_async().await;
+        let mock = server
+            .mock(
+                "GET",
+                "/xlive/web-room/v1/giftPanel/giftConfig?platform=pc&room_id=1",
+            )
+            .with_body(
+                r#"{"code":0,"data":{"list":[{"id":1,"name":"辣条","price":100,"coin_type":"silver"}]}}"#,
+            )
+            .expect(1)
+            .create_async()
+            .await;
+
+        let path = std::env::temp_dir()
+            .join(format!("bilicli-test-download-{}", std::process::id()))
+            .join("gifts.json");
+        let api = ApiClient::builder().base_url(server.url()).build();
+
+        let catalog = GiftCatalog::load_from(&api, 1, Some(&path)).await.unwrap();
+        assert_eq!(catalog.get(1).unwrap().coin_type, "silver");
+
+        // the second load is served from disk
+        let catalog = GiftCatalog::load_from(&api, 1, Some(&path)).await.unwrap();
+        assert_eq!(catalog.get(1).unwrap().name, "辣条");
+        mock.assert_async().await;
+
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn values_gifts_by_coin_type() {
+        assert_eq!(
+            GiftValue::new("gold", 100, 3).text().unwrap(),
+            "0.3 元 / 3 电池"
+        );
+        assert_eq!(
+            GiftValue::new("silver", 100, 3).text().unwrap(),
+            "300 银瓜子"
+        );
+        assert_eq!(GiftValue::new("gold", 0, 3), GiftValue::Free);
+    }
+}
@@ -1,19 +1,33 @@
 #[cfg(feature = "platform-napi")]
-use std::{io::stdout, sync::Arc};
+use std::sync::Arc;
+use std::{
+    collections::{BTreeMap, HashMap},
+    io::stdout,
+    time::{Duration, Instant},
+};
 
+use crate::ui::{
+    emoticons::EmoticonTable, AppState, ConnectionState, DanmuMsg, DisconnectMsg, MsgType,
+    RoomInfoState, RoomQuery,
+};
 #[cfg(feature = "platform-napi")]
 use crate::{
-    api::get_room_info,
+    api::live::{listen, LiveConfig},
     app::App,
-    ui::{AppState, MsgType},
 };
 
-use api::RoomInfo;
+use api::{
+    live::{dispatch, history_danmu, LiveCommand, LiveEvent, RoomChange},
+    parse_room_input, ApiClient, ApiError, ApiResult, EmoticonPackage, GuardMember, HistoryDanmu,
+    OnlineRank, OnlineRankUser, ProxyConfig, RoomInfo, StreamerCandidate,
+};
 use chrono::NaiveDateTime;
+use gift_catalog::GiftCatalog;
 #[cfg(feature = "platform-napi")]
 use napi::bindgen_prelude::*;
 #[cfg(feature = "platform-napi")]
 use napi_derive::napi;
+use serde::Serialize;
 
 #[cfg(feature = "platform-napi")]
 use ratatui::{
@@ -38,12 +52,14 @@ use ui::SliderBarState;
 
 pub mod api;
 pub mod app;
+pub mod gift_catalog;
+pub mod paths;
+pub mod send_queue;
 pub mod ui;
 
 #[cfg(feature = "platform-napi")]
 #[napi]
 pub struct Tui {
-    room_id: u32,
     app: Arc<Mutex<App>>,
     state: Arc<Mutex<TuiState>>,
 }
@@ -52,12 +68,30 @@ pub struct Tui {
 #[napi]
 impl Tui {
     #[napi(constructor)]
-    pub fn new(room_id: u32, cookie: Option<String>) -> Result<Self> {
-        Ok(Self {
-            room_id,
+    pub fn new(room: String, cookie: Option<String>) -> Result<Self> {
+        Ok(Self::from_state(TuiState::new(&room, cookie)))
+    }
+
+    /// Open the live room of the streamer with the given UID.
+    #[napi(factory)]
+    pub fn with_streamer_uid(uid: i64, cookie: Option<String>) -> Result<Self> {
+        let query = RoomQuery::StreamerUid(uid as u64);
+        Ok(Self::from_state(TuiState::with_query(query, cookie)))
+    }
+
+    /// Search streamers by name and open the live room of the match, letting the user
+    /// pick one in the TUI when several streamers match.
+    #[napi(factory)]
+    pub fn with_streamer_name(name: String, cookie: Option<String>) -> Result<Self> {
+        let query = RoomQuery::StreamerName(name);
+        Ok(Self::from_state(TuiState::with_query(query, cookie)))
+    }
+
+    fn from_state(state: TuiState) -> Self {
+        Self {
             app: Arc::new(Mutex::new(App::default())),
-            state: Arc::new(Mutex::new(TuiState::new(room_id, cookie))),
-        })
+            state: Arc::new(Mutex::new(state)),
+        }
     }
 
     #[napi(getter)]
@@ -65,6 +99,50 @@ impl Tui {
         self.state.lock().await.state
     }
 
+    /// the proxy every request and the danmaku connection go through, without credentials
+    #[napi(getter)]
+    pub async fn proxy(&self) -> Option<String> {
+        let state = self.state.lock().await;
+        state.api.proxy().map(|proxy| proxy.to_string())
+    }
+
+    /// live commands received by `listen` that have no mapping yet, with their counts
+    #[napi(getter)]
+    pub async fn unknown_commands(&self) -> HashMap<String, u32> {
+        self.state
+            .lock()
+            .await
+            .unknown_commands
+            .clone()
+            .into_iter()
+            .collect()
+    }
+
+    /// Resolve the room given to the constructor (short id, long id or url) to the
+    /// canonical room id, or `null` if it cannot be resolved.
+    #[napi]
+    pub async fn resolve_room(&self) -> Option<u32> {
+        self.state.lock().await.resolve_room().await.ok().flatten()
+    }
+
+    /// Wait until the room has been resolved, which may need the user to pick a streamer
+    /// or retry in the TUI, and return the canonical room id, or `null` if the TUI quit first.
+    #[napi]
+    pub async fn wait_room(&self) -> Option<u32> {
+        loop {
+            {
+                let state = self.state.lock().await;
+                if state.room_resolved {
+                    return Some(state.room_id);
+                }
+                if state.state == AppState::Quit {
+                    return None;
+                }
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+        }
+    }
+
     /// # Safety
     /// This function is marked as unsafe because it requires exclusive access to the state.
     #[napi]
@@ -72,8 +150,9 @@ impl Tui {
         let state = Arc::clone(&self.state);
         tokio::spawn(async move {
             let mut state = state.lock().await;
-            let info = get_room_info(state.room_id).await.unwrap();
-            state.update_info(info);
+            if state.room_info == RoomInfoState::Loading {
+                state.refresh_room_info().await;
+            }
         })
         .await
         .unwrap_or_default();
@@ -103,6 +182,38 @@ impl Tui {
         Ok(())
     }
 
+    /// Override the danmaku length limit, which otherwise follows the account's level.
+    #[napi]
+    pub async fn set_input_limit(&self, limit: Option<u32>) {
+        self.app.lock().await.input_limit_override = limit.map(|limit| limit as usize);
+    }
+
+    /// Route every request and the danmaku connection through an `http://`, `https://`,
+    /// `socks5://` or `socks5h://` proxy, optionally with `user:password@`. Without a url
+    /// the proxy is read from `ALL_PROXY`, `HTTPS_PROXY` or `HTTP_PROXY`, skipping the
+    /// hosts in `NO_PROXY`. An invalid url is an error.
+    #[napi]
+    pub async fn set_proxy(&self, url: Option<String>) -> Result<()> {
+        let proxy = match url.filter(|url| !url.trim().is_empty()) {
+            Some(url) => Some(ProxyConfig::parse(&url)),
+            None => ProxyConfig::from_env(),
+        }
+        .transpose()
+        .map_err(|err| Error::from_reason(err.to_string()))?;
+
+        self.state
+            .lock()
+            .await
+            .set_proxy(proxy)
+            .map_err(|err| Error::from_reason(err.to_string()))
+    }
+
+    /// Split messages longer than the limit into several danmaku, toggled with Ctrl+T.
+    #[napi]
+    pub async fn set_split_long_danmu(&self, split: bool) {
+        self.app.lock().await.split_long_danmu = split;
+    }
+
     /// # Safety
     /// This function is marked as unsafe because it requires exclusive access to the state.
     #[napi]
@@ -113,14 +224,36 @@ impl Tui {
         Ok(())
     }
 
+    /// Fetch the room's danmaku hosts, connect to them natively and feed the events
+    /// into the state, instead of pushing them through `send_msg`.
+    ///
+    /// # Safety
+    /// This function is marked as unsafe because it requires exclusive access to the state.
+    #[napi]
+    pub async unsafe fn listen(&mut self, uid: Option<i64>) {
+        let room_id = {
+            let mut state = self.state.lock().await;
+            let _ = state.resolve_room().await;
+            state.room_id
+        };
+        let mut config = LiveConfig::new(room_id);
+        config.uid = uid.unwrap_or_default() as u64;
+        config.fetch_danmu_info = true;
+
+        tokio::spawn(listen(config, Arc::clone(&self.state)));
+    }
+
     /// # Safety
     /// This function is marked as unsafe because it requires exclusive access to the state.
     #[napi]
     pub async unsafe fn send_attention_change(&mut self, attention: u32) {
         if attention == 1 {
-            let info = get_room_info(self.room_id).await.unwrap();
-            let mut state = self.state.lock().await;
-            state.update_info(info);
+            let (api, room_id) = {
+                let state = self.state.lock().await;
+                (state.api.clone(), state.room_id)
+            };
+            let result = api.get_room_info(room_id).await;
+            self.state.lock().await.apply_room_info(result);
         } else {
             let mut state = self.state.lock().await;
             state.update_attention(attention);
@@ -175,9 +308,29 @@ pub fn init_panic_hook() {
 #[derive(Clone, Default, Debug)]
 pub struct TuiState {
     pub cookie: Option<String>,
+    pub api: ApiClient,
     pub slider_bar_state: SliderBarState,
     pub state: AppState,
+    pub connection: ConnectionState,
+    /// why the danmaku connection was lost, or why the last attempt to connect failed
+    pub connection_error: Option<String>,
+    /// why the proxy from the environment cannot be used, refusing every network call
+    /// until `set_proxy` replaces it
+    pub proxy_error: Option<String>,
+    pub room_info: RoomInfoState,
+    /// set by the UI to ask for a room info refresh on the next frame
+    pub room_info_requested: bool,
+    /// the room as given on the command line
+    pub room_query: RoomQuery,
+    /// streamers matching a name query, waiting for the user to pick one
+    pub streamer_candidates: Vec<StreamerCandidate>,
+    /// whether `room_id` holds the canonical id returned by the room-init endpoint
+    pub room_resolved: bool,
     pub room_id: u32,
+    /// the vanity room number, `0` if the room has none
+    pub short_id: u32,
+    /// the uid of the streamer owning the room
+    pub streamer_uid: u64,
     pub attention: u32,
     pub watchers: u32,
     pub is_live: bool,
@@ -186,20 +339,108 @@ pub struct TuiState {
     pub parent_area_name: String,
     pub title: String,
     pub messages: Vec<(MsgType, String)>,
+    /// the index into `messages` selected in the current tab
+    pub selected_message: Option<usize>,
+    /// the length of `messages` when the danmaku in flight started sending; only messages
+    /// from here on can be its copy from the live stream
+    pub echo_from: usize,
+    /// the uid danmaku are sent as, taken from the send response since the cookie may
+    /// not carry `DedeUserID`
+    pub sender_uid: Option<u64>,
+    /// how often each live command without a mapping has been received
+    pub unknown_commands: BTreeMap<String, u32>,
+    /// the emoticon packages of each room, fetched once when first needed
+    pub room_emoticons: HashMap<u32, Vec<EmoticonPackage>>,
+    /// the gifts of the room, loaded once with the room info
+    pub gift_catalog: GiftCatalog,
+    pub gift_catalog_loaded: bool,
+    /// how emoticon tokens are shown, extended with the emoticons of the room once fetched
+    pub emoticon_table: EmoticonTable,
+    /// whether the danmaku history of the room has been loaded into `messages`
+    pub history_loaded: bool,
+    /// the top viewers of the online gold rank
+    pub online_rank: Vec<OnlineRankUser>,
+    /// how many viewers are ranked, updated by `ONLINE_RANK_COUNT`
+    pub online_rank_count: u32,
+    /// set when a rank event arrives to refresh the rank sooner than the timer
+    pub online_rank_requested: bool,
+    pub online_rank_updated_at: Option<Instant>,
+    /// every guard of the room, `None` until the roster is first opened
+    pub guards: Option<Vec<GuardMember>>,
+    /// set by the roster to fetch the guards on the next frame
+    pub guards_requested: bool,
+    /// set by a guard purchase to fetch the guards once `GUARDS_MIN_INTERVAL` has passed
+    pub guards_stale: bool,
+    pub guards_updated_at: Option<Instant>,
 }
 
 impl TuiState {
-    pub fn new(room_id: u32, cookie: Option<String>) -> Self {
-        Self {
-            room_id,
+    /// open `room`, searching it as a streamer name when it is neither a room id nor a url
+    pub fn new(room: &str, cookie: Option<String>) -> Self {
+        let query = if parse_room_input(room).is_none() && !room.contains("bilibili.com") {
+            RoomQuery::StreamerName(room.trim().to_string())
+        } else {
+            RoomQuery::Room(room.to_string())
+        };
+
+        Self::with_query(query, cookie)
+    }
+
+    pub fn with_query(room_query: RoomQuery, cookie: Option<String>) -> Self {
+        let mut state = Self {
+            room_id: match &room_query {
+                RoomQuery::Room(room) => parse_room_input(room).unwrap_or_default(),
+                _ => 0,
+            },
+            room_query,
+            api: ApiClient::new(cookie.clone()),
             cookie,
             slider_bar_state: SliderBarState::Hiding,
+            emoticon_table: EmoticonTable::new(),
             ..Default::default()
+        };
+
+        match ProxyConfig::from_env() {
+            Some(Ok(proxy)) => {
+                if let Err(err) = state.set_proxy(Some(proxy)) {
+                    state.refuse_network(err.to_string());
+                }
+            }
+            Some(Err(err)) => state.refuse_network(err.to_string()),
+            None => {}
         }
+
+        state
     }
 }
 
 impl TuiState {
+    const ONLINE_RANK_INTERVAL: Duration = Duration::from_secs(30);
+    const ONLINE_RANK_MIN_INTERVAL: Duration = Duration::from_secs(5);
+    /// guard purchases come in bursts, and every refresh pages through the whole roster
+    const GUARDS_MIN_INTERVAL: Duration = Duration::from_secs(30);
+
+    /// route every request and the danmaku connection through `proxy`, keeping the
+    /// current client if the proxy is rejected
+    pub fn set_proxy(&mut self, proxy: Option<ProxyConfig>) -> ApiResult<()> {
+        self.api = ApiClient::builder()
+            .cookie(self.cookie.clone())
+            .proxy(proxy)
+            .try_build()?;
+        self.proxy_error = None;
+        Ok(())
+    }
+
+    /// refuse every request and the danmaku connection until a usable proxy is set, as
+    /// connecting directly would bypass the proxy that was asked for
+    fn refuse_network(&mut self, reason: String) {
+        self.api = ApiClient::builder()
+            .cookie(self.cookie.clone())
+            .proxy_error(Some(reason.clone()))
+            .build();
+        self.proxy_error = Some(reason);
+    }
+
     pub fn quit(&mut self) {
         if self.state == AppState::Quit {
             return;
@@ -214,6 +455,8 @@ impl TuiState {
     }
 
     pub fn update_info(&mut self, info: RoomInfo) {
+        self.short_id = info.short_id;
+        self.streamer_uid = info.uid;
         self.area_name = info.area_name;
         self.parent_area_name = info.parent_area_name;
         self.title = info.title;
@@ -224,6 +467,180 @@ impl TuiState {
         self.watchers = info.online;
     }
 
+    /// whether the logged in account is the streamer of the room
+    pub fn is_anchor(&self) -> bool {
+        self.streamer_uid != 0 && self.api.uid() == Some(self.streamer_uid)
+    }
+
+    /// resolve `room_query` to the canonical room id once, `None` while the user still
+    /// has to pick one of several matching streamers
+    pub async fn resolve_room(&mut self) -> ApiResult<Option<u32>> {
+        if self.room_resolved {
+            return Ok(Some(self.room_id));
+        }
+
+        let room = match &self.room_query {
+            RoomQuery::Room(room) => self.api.resolve_room(room).await?,
+            RoomQuery::StreamerUid(uid) => {
+                let room = self.api.get_room_by_uid(*uid).await?;
+                self.api.room_init(room.room_id).await?
+            }
+            RoomQuery::StreamerName(_) if !self.streamer_candidates.is_empty() => {
+                return Ok(None);
+            }
+            RoomQuery::StreamerName(name) => {
+                let mut candidates = self.api.search_streamers(name).await?;
+                match candidates.len() {
+                    0 => return Err(ApiError::StreamerNotFound(name.clone())),
+                    1 => self.api.room_init(candidates.remove(0).room_id).await?,
+                    _ => {
+                        self.streamer_candidates = candidates;
+                        return Ok(None);
+                    }
+                }
+            }
+        };
+
+        self.room_id = room.room_id;
+        self.short_id = room.short_id;
+        self.room_resolved = true;
+
+        Ok(Some(self.room_id))
+    }
+
+    /// open the room of the picked streamer candidate
+    pub fn pick_streamer(&mut self, index: usize) {
+        if let Some(candidate) = self.streamer_candidates.get(index) {
+            self.room_query = RoomQuery::Room(candidate.room_id.to_string());
+            self.room_id = candidate.room_id;
+            self.streamer_candidates.clear();
+            self.room_info_requested = true;
+        }
+    }
+
+    pub async fn refresh_room_info(&mut self) {
+        match self.resolve_room().await {
+            Ok(Some(_)) => {}
+            // waiting for the user to pick a streamer
+            Ok(None) => return,
+            Err(err) => {
+                self.apply_room_info(Err(err));
+                return;
+            }
+        }
+
+        let result = self.api.get_room_info(self.room_id).await;
+        self.apply_room_info(result);
+
+        if !self.gift_catalog_loaded {
+            // without the catalog gifts are shown with the data of the message alone,
+            // until a later refresh loads it
+            if let Ok(catalog) = GiftCatalog::load(&self.api, self.room_id).await {
+                self.gift_catalog = catalog;
+                self.gift_catalog_loaded = true;
+            }
+        }
+
+        if !self.history_loaded {
+            if let Ok(history) = self.api.get_history(self.room_id).await {
+                self.prepend_history(history);
+                self.history_loaded = true;
+            }
+        }
+
+        // the emoticons of a room are only listed to logged in accounts
+        if self.cookie.is_some() && !self.room_emoticons.contains_key(&self.room_id) {
+            if let Ok(packages) = self.api.get_emoticons(self.room_id).await {
+                self.set_room_emoticons(self.room_id, packages);
+            }
+        }
+    }
+
+    /// whether the online rank should be fetched again, either on the timer or because a
+    /// rank event arrived, but not more often than `ONLINE_RANK_MIN_INTERVAL`
+    pub fn online_rank_due(&self) -> bool {
+        if !self.room_resolved || self.streamer_uid == 0 {
+            return false;
+        }
+
+        match self.online_rank_updated_at {
+            None => true,
+            Some(updated_at) => {
+                let elapsed = updated_at.elapsed();
+                elapsed >= Self::ONLINE_RANK_INTERVAL
+                    || (self.online_rank_requested && elapsed >= Self::ONLINE_RANK_MIN_INTERVAL)
+            }
+        }
+    }
+
+    /// mark the online rank as being fetched, the result is handed to `apply_online_rank`
+    pub fn start_online_rank_refresh(&mut self) {
+        self.online_rank_requested = false;
+        self.online_rank_updated_at = Some(Instant::now());
+    }
+
+    pub fn apply_online_rank(&mut self, result: ApiResult<OnlineRank>) {
+        // keep showing the previous rank when the refresh fails
+        if let Ok(rank) = result {
+            self.online_rank_count = rank.online_num;
+            self.online_rank = rank.users;
+        }
+    }
+
+    /// whether the guards should be fetched, right away when the roster asks for them
+    /// and at most every `GUARDS_MIN_INTERVAL` after guard purchases
+    pub fn guards_due(&self) -> bool {
+        match self.guards_updated_at {
+            _ if self.guards_requested => true,
+            Some(updated_at) if self.guards_stale => {
+                updated_at.elapsed() >= Self::GUARDS_MIN_INTERVAL
+            }
+            _ => self.guards_stale,
+        }
+    }
+
+    /// mark the guards as being fetched, the result is handed to `apply_guards`
+    pub fn start_guards_refresh(&mut self) {
+        self.guards_requested = false;
+        self.guards_stale = false;
+        self.guards_updated_at = Some(Instant::now());
+    }
+
+    pub fn apply_guards(&mut self, result: ApiResult<Vec<GuardMember>>) -> ApiResult<()> {
+        match result {
+            Ok(guards) => {
+                self.guards = Some(guards);
+                Ok(())
+            }
+            Err(err) => {
+                self.guards.get_or_insert_with(Vec::new);
+                Err(err)
+            }
+        }
+    }
+
+    pub fn set_room_emoticons(&mut self, room_id: u32, packages: Vec<EmoticonPackage>) {
+        self.emoticon_table.merge_room(&packages);
+        self.room_emoticons.insert(room_id, packages);
+    }
+
+    /// apply a room info response, keeping the previous info on failure
+    pub fn apply_room_info(&mut self, result: ApiResult<RoomInfo>) {
+        self.room_info = match result {
+            Ok(info) => {
+                self.update_info(info);
+                RoomInfoState::Loaded
+            }
+            Err(err) => RoomInfoState::Failed {
+                message: err.to_string(),
+                loaded: matches!(
+                    self.room_info,
+                    RoomInfoState::Loaded | RoomInfoState::Failed { loaded: true, .. }
+                ),
+            },
+        };
+    }
+
     pub fn update_attention(&mut self, attention: u32) {
         self.attention = attention;
     }
@@ -235,4 +652,197 @@ impl TuiState {
     pub fn update_live(&mut self, live: bool) {
         self.is_live = live;
     }
+
+    pub fn update_room(&mut self, change: RoomChange) {
+        self.title = change.title;
+        self.area_name = change.area_name;
+        self.parent_area_name = change.parent_area_name;
+    }
+
+    pub fn push_message(&mut self, t: MsgType, msg: &impl Serialize) {
+        if let Ok(msg) = serde_json::to_string(msg) {
+            self.messages.push((t, msg));
+        }
+    }
+
+    /// put the danmaku sent before we joined in front of every message received since
+    pub fn prepend_history(&mut self, history: Vec<HistoryDanmu>) {
+        let messages: Vec<_> = history
+            .into_iter()
+            .filter_map(|danmu| {
+                let msg = history_danmu(danmu, self.room_id);
+                serde_json::to_string(&msg)
+                    .ok()
+                    .map(|msg| (MsgType::Danmu, msg))
+            })
+            .collect();
+
+        if let Some(selected) = &mut self.selected_message {
+            *selected += messages.len();
+        }
+        self.echo_from += messages.len();
+        self.messages.splice(0..0, messages);
+    }
+
+    /// how many of the latest messages are searched for the echo of a danmaku
+    const ECHO_LOOKBACK: usize = 200;
+
+    /// remember where to look for the live copy of the danmaku that starts sending now
+    pub fn start_echo(&mut self) {
+        self.echo_from = self.messages.len();
+    }
+
+    /// show a danmaku we sent right away, unless the live stream was faster since it
+    /// started sending
+    pub fn push_echo(&mut self, msg: DanmuMsg) {
+        if msg.user().uid != 0 {
+            self.sender_uid = Some(msg.user().uid);
+        }
+        if self.find_echo(&msg, false).is_none() {
+            self.push_message(MsgType::Danmu, &msg.into_pending());
+        }
+    }
+
+    /// push a danmaku from the live stream, replacing the echo of our own message in place
+    pub fn push_danmu(&mut self, msg: DanmuMsg) {
+        match self.find_echo(&msg, true) {
+            Some(index) => {
+                if let Ok(json) = serde_json::to_string(&msg) {
+                    self.messages[index].1 = json;
+                }
+            }
+            None => self.push_message(MsgType::Danmu, &msg),
+        }
+    }
+
+    /// the oldest recent danmaku with the sender and content of `msg` that is pending or not
+    fn find_echo(&self, msg: &DanmuMsg, pending: bool) -> Option<usize> {
+        // without a known uid on either side only the content can tell
+        let uid = msg.user().uid;
+        let own_uid = self.sender_uid.or_else(|| self.api.uid());
+        if uid != 0 && own_uid.is_some_and(|own_uid| own_uid != uid) {
+            return None;
+        }
+        let same_sender = |other: u64| uid == 0 || other == 0 || other == uid;
+
+        let mut start = self.messages.len().saturating_sub(Self::ECHO_LOOKBACK);
+        if !pending {
+            // an earlier danmaku with the same text is not the copy of this one
+            start = start.max(self.echo_from);
+        }
+        (start..self.messages.len()).find(|&index| {
+            let (t, json) = &self.messages[index];
+            *t == MsgType::Danmu
+                && serde_json::from_str::<DanmuMsg>(json).is_ok_and(|echo| {
+                    echo.is_pending() == pending
+                        && !echo.is_history()
+                        && same_sender(echo.user().uid)
+                        && echo.content() == msg.content()
+                })
+        })
+    }
+
+    /// insert a marker into every tab for the time the connection was down
+    pub fn mark_disconnected(&mut self, start: i64, end: i64) {
+        self.push_message(MsgType::Disconnect, &DisconnectMsg { start, end });
+    }
+
+    pub fn handle_live_event(&mut self, event: LiveEvent) {
+        let LiveEvent::Command(command) = event else {
+            // the heartbeat popularity is superseded by `WATCHED_CHANGE`
+            return;
+        };
+
+        match dispatch(&command, self.room_id) {
+            LiveCommand::Danmu(msg) => self.push_danmu(msg),
+            LiveCommand::SuperChat(msg) => self.push_message(MsgType::SuperChat, &msg),
+            LiveCommand::Gift(msg) => self.push_message(MsgType::Gift, &msg),
+            LiveCommand::GuardBuy(msg) => {
+                self.push_message(MsgType::GuardBuy, &msg);
+                // only keep the roster up to date once it has been opened
+                self.guards_stale |= self.guards.is_some();
+            }
+            LiveCommand::UserAction(msg) => self.push_message(MsgType::UserAction, &msg),
+            LiveCommand::Watcher(watcher) => self.update_watcher(watcher),
+            LiveCommand::Attention(attention) => self.update_attention(attention),
+            LiveCommand::Live(live) => self.update_live(live),
+            LiveCommand::RoomChange(change) => self.update_room(change),
+            LiveCommand::OnlineRankCount(count) => {
+                self.online_rank_count = count;
+                self.online_rank_requested = true;
+            }
+            LiveCommand::OnlineRankChanged => self.online_rank_requested = true,
+            LiveCommand::Unknown(cmd) => *self.unknown_commands.entry(cmd).or_default() += 1,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ui::User;
+
+    fn danmu(uid: u64, content: &str) -> DanmuMsg {
+        let user = User {
+            uid,
+            uname: "me".to_string(),
+            face: None,
+            badge: None,
+            identity: None,
+        };
+        DanmuMsg::new(user, content.to_string(), 0, false, None)
+    }
+
+    fn danmu_at(state: &TuiState, index: usize) -> DanmuMsg {
+        serde_json::from_str(&state.messages[index].1).unwrap()
+    }
+
+    #[test]
+    fn reconciles_echo_with_live_danmaku() {
+        let mut state = TuiState::new("1", Some("DedeUserID=42; bili_jct=x".to_string()));
+
+        state.push_echo(danmu(42, "hello"));
+        state.push_danmu(danmu(7, "hello"));
+        assert!(danmu_at(&state, 0).is_pending());
+
+        state.push_danmu(danmu(42, "hello"));
+        assert_eq!(state.messages.len(), 2);
+        assert!(!danmu_at(&state, 0).is_pending());
+
+        // the live stream was faster than the send response
+        state.start_echo();
+        state.push_danmu(danmu(42, "again"));
+        state.push_echo(danmu(42, "again"));
+        assert_eq!(state.messages.len(), 3);
+
+        // sending the same text again still echoes it, and the live copy confirms it
+        state.start_echo();
+        state.push_echo(danmu(42, "again"));
+        assert_eq!(state.messages.len(), 4);
+        assert!(danmu_at(&state, 3).is_pending());
+        state.push_danmu(danmu(42, "again"));
+        assert_eq!(state.messages.len(), 4);
+        assert!(!danmu_at(&state, 3).is_pending());
+    }
+
+    #[test]
+    fn reconciles_echo_without_uid_in_cookie() {
+        let mut state = TuiState::new("1", Some("SESSDATA=abc; bili_jct=x".to_string()));
+
+        // the uid comes from the send response
+        state.start_echo();
+        state.push_echo(danmu(42, "hello"));
+        state.push_danmu(danmu(7, "hello"));
+        state.push_danmu(danmu(42, "hello"));
+        assert_eq!(state.messages.len(), 2);
+        assert!(!danmu_at(&state, 0).is_pending());
+
+        // neither side knows the uid, the content in the echo window decides
+        let mut state = TuiState::new("1", Some("SESSDATA=abc; bili_jct=x".to_string()));
+        state.start_echo();
+        state.push_echo(danmu(0, "hello"));
+        state.push_danmu(danmu(0, "hello"));
+        assert_eq!(state.messages.len(), 1);
+        assert!(!danmu_at(&state, 0).is_pending());
+    }
 }
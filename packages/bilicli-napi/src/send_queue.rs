@@ -0,0 +1,384 @@
+use std::{
+    collections::VecDeque,
+    time::{Duration, Instant},
+};
+
+use crate::api::{live::Backoff, ApiError, SendDanmuOptions};
+
+/// the shortest time between two sent danmaku, the server rejects faster ones as too frequent
+pub const SEND_INTERVAL: Duration = Duration::from_millis(1000);
+
+const RETRY_MIN_DELAY: Duration = Duration::from_secs(2);
+const RETRY_MAX_DELAY: Duration = Duration::from_secs(30);
+const MAX_RETRIES: u32 = 5;
+
+/// a danmaku waiting to be sent
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DanmuContent {
+    Text(String),
+    /// a room emoticon, sent by its unique id and shown by its emoji text
+    Emoticon {
+        unique: String,
+        emoji: String,
+    },
+}
+
+impl DanmuContent {
+    /// the text shown for this danmaku in the UI
+    pub fn text(&self) -> &str {
+        match self {
+            DanmuContent::Text(text) => text,
+            DanmuContent::Emoticon { emoji, .. } => emoji,
+        }
+    }
+}
+
+/// the length of `text` as Bilibili counts it, in UTF-16 code units like a JS string
+pub fn danmu_length(text: &str) -> usize {
+    text.encode_utf16().count()
+}
+
+fn is_split_boundary(c: char) -> bool {
+    c.is_whitespace() || "，。！？、；：…~）】」,.!?;:)".contains(c)
+}
+
+/// split `text` into danmaku of at most `limit`, preferring to cut after punctuation
+/// or whitespace in the second half of each part
+pub fn split_danmu(text: &str, limit: usize) -> Vec<String> {
+    let mut parts = Vec::new();
+    let mut rest: Vec<char> = text.trim().chars().collect();
+
+    while limit > 0 && rest.iter().map(|c| c.len_utf16()).sum::<usize>() > limit {
+        let mut end = 0;
+        let mut length = 0;
+        for c in &rest {
+            if length + c.len_utf16() > limit {
+                break;
+            }
+            length += c.len_utf16();
+            end += 1;
+        }
+
+        let cut = rest[..end]
+            .iter()
+            .rposition(|c| is_split_boundary(*c))
+            .map(|index| index + 1)
+            .filter(|cut| *cut > end / 2)
+            .unwrap_or(end.max(1));
+        parts.push(rest[..cut].iter().collect::<String>().trim().to_string());
+        rest.drain(..cut);
+        while rest.first().is_some_and(|c| c.is_whitespace()) {
+            rest.remove(0);
+        }
+    }
+
+    parts.push(rest.into_iter().collect());
+    parts.retain(|part| !part.is_empty());
+    parts
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SendStatus {
+    Queued,
+    Sending,
+    /// rejected as too frequent, sent again at `retry_at`
+    Retrying {
+        attempt: u32,
+        retry_at: Instant,
+    },
+    Failed(String),
+}
+
+#[derive(Debug, Clone)]
+pub struct QueuedDanmu {
+    pub id: u64,
+    pub content: DanmuContent,
+    pub options: SendDanmuOptions,
+    pub status: SendStatus,
+    /// the id of the first part of the message this danmaku was split from
+    group: u64,
+    backoff: Backoff,
+}
+
+/// outgoing danmaku, sent one at a time and no faster than `interval`
+#[derive(Debug)]
+pub struct SendQueue {
+    items: VecDeque<QueuedDanmu>,
+    interval: Duration,
+    last_sent: Option<Instant>,
+    next_id: u64,
+}
+
+impl Default for SendQueue {
+    fn default() -> Self {
+        Self::new(SEND_INTERVAL)
+    }
+}
+
+impl SendQueue {
+    pub fn new(interval: Duration) -> Self {
+        Self {
+            items: VecDeque::new(),
+            interval,
+            last_sent: None,
+            next_id: 0,
+        }
+    }
+
+    pub fn push(&mut self, content: DanmuContent, options: SendDanmuOptions) -> u64 {
+        self.push_to_group(content, options, self.next_id)
+    }
+
+    /// queue the parts of one split message, which are only sent in order: while a part
+    /// has failed the parts after it are held back
+    pub fn push_parts(
+        &mut self,
+        parts: impl IntoIterator<Item = (DanmuContent, SendDanmuOptions)>,
+    ) -> Vec<u64> {
+        let group = self.next_id;
+        parts
+            .into_iter()
+            .map(|(content, options)| self.push_to_group(content, options, group))
+            .collect()
+    }
+
+    fn push_to_group(
+        &mut self,
+        content: DanmuContent,
+        options: SendDanmuOptions,
+        group: u64,
+    ) -> u64 {
+        let id = self.next_id;
+        self.next_id += 1;
+        self.items.push_back(QueuedDanmu {
+            id,
+            content,
+            options,
+            status: SendStatus::Queued,
+            group,
+            backoff: Backoff::new(RETRY_MIN_DELAY, RETRY_MAX_DELAY),
+        });
+        id
+    }
+
+    pub fn items(&self) -> impl Iterator<Item = &QueuedDanmu> {
+        self.items.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    /// whether `item` waits for an earlier part of its message that failed
+    pub fn is_held(&self, item: &QueuedDanmu) -> bool {
+        self.items
+            .iter()
+            .take_while(|other| other.id != item.id)
+            .any(|other| other.group == item.group && matches!(other.status, SendStatus::Failed(_)))
+    }
+
+    /// the item to send now, marked as sending; `None` while one is in flight,
+    /// the pacing interval has not passed, or the next item waits for its retry
+    pub fn next_ready(&mut self, now: Instant) -> Option<QueuedDanmu> {
+        if self
+            .items
+            .iter()
+            .any(|item| item.status == SendStatus::Sending)
+        {
+            return None;
+        }
+
+        if let Some(last_sent) = self.last_sent {
+            if now < last_sent + self.interval {
+                return None;
+            }
+        }
+
+        // keep the order: a danmaku waiting for its retry holds back the ones behind it,
+        // and a failed part holds back the rest of its message
+        let mut failed_groups = Vec::new();
+        let item = self.items.iter_mut().find(|item| {
+            if let SendStatus::Failed(_) = item.status {
+                failed_groups.push(item.group);
+                return false;
+            }
+            !failed_groups.contains(&item.group)
+        })?;
+        match item.status {
+            SendStatus::Retrying { retry_at, .. } if now < retry_at => None,
+            _ => {
+                item.status = SendStatus::Sending;
+                Some(item.clone())
+            }
+        }
+    }
+
+    /// record the result of sending `id`, scheduling a retry if it was too frequent
+    pub fn complete(&mut self, id: u64, result: Result<(), ApiError>, now: Instant) {
+        self.last_sent = Some(now);
+        let Some(index) = self.items.iter().position(|item| item.id == id) else {
+            return;
+        };
+
+        let item = &mut self.items[index];
+        match result {
+            Ok(()) => {
+                self.items.remove(index);
+            }
+            Err(err) if err.is_too_frequent() && item.backoff.attempt() < MAX_RETRIES => {
+                let delay = item.backoff.next_delay();
+                item.status = SendStatus::Retrying {
+                    attempt: item.backoff.attempt(),
+                    retry_at: now + delay,
+                };
+            }
+            Err(err) => item.status = SendStatus::Failed(err.to_string()),
+        }
+    }
+
+    /// drop `id` unless it is being sent right now; dropping a failed part also drops
+    /// the parts of its message after it, which would make no sense on their own
+    pub fn cancel(&mut self, id: u64) -> bool {
+        let index = self
+            .items
+            .iter()
+            .position(|item| item.id == id && item.status != SendStatus::Sending);
+        let Some(item) = index.and_then(|index| self.items.remove(index)) else {
+            return false;
+        };
+
+        if let SendStatus::Failed(_) = item.status {
+            self.items.retain(|other| {
+                other.group != item.group
+                    || other.id < item.id
+                    || other.status == SendStatus::Sending
+            });
+        }
+        true
+    }
+
+    /// queue a failed item again
+    pub fn retry(&mut self, id: u64) {
+        if let Some(item) = self.items.iter_mut().find(|item| item.id == id) {
+            if let SendStatus::Failed(_) = item.status {
+                item.status = SendStatus::Queued;
+                item.backoff.reset();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn text(text: &str) -> DanmuContent {
+        DanmuContent::Text(text.to_string())
+    }
+
+    #[test]
+    fn paces_messages() {
+        let mut queue = SendQueue::new(Duration::from_secs(1));
+        let now = Instant::now();
+        let first = queue.push(text("1"), SendDanmuOptions::default());
+        let second = queue.push(text("2"), SendDanmuOptions::default());
+
+        assert_eq!(queue.next_ready(now).unwrap().id, first);
+        assert!(queue.next_ready(now).is_none());
+        queue.complete(first, Ok(()), now);
+
+        assert!(queue.next_ready(now + Duration::from_millis(500)).is_none());
+        assert_eq!(
+            queue.next_ready(now + Duration::from_secs(1)).unwrap().id,
+            second
+        );
+    }
+
+    #[test]
+    fn retries_too_frequent_with_backoff() {
+        let mut queue = SendQueue::new(Duration::ZERO);
+        let now = Instant::now();
+        let id = queue.push(text("1"), SendDanmuOptions::default());
+
+        queue.next_ready(now);
+        queue.complete(id, Err(ApiError::business(10030, "")), now);
+        assert!(matches!(
+            queue.items().next().unwrap().status,
+            SendStatus::Retrying { attempt: 1, .. }
+        ));
+        assert!(queue.next_ready(now + Duration::from_secs(1)).is_none());
+        assert_eq!(queue.next_ready(now + RETRY_MIN_DELAY).unwrap().id, id);
+
+        queue.complete(id, Err(ApiError::business(1003, "")), now);
+        assert!(matches!(
+            queue.items().next().unwrap().status,
+            SendStatus::Failed(_)
+        ));
+        assert!(queue.next_ready(now + RETRY_MAX_DELAY).is_none());
+
+        queue.retry(id);
+        assert!(queue.cancel(id));
+        assert!(queue.is_empty());
+    }
+
+    #[test]
+    fn holds_parts_after_a_failed_part() {
+        let mut queue = SendQueue::new(Duration::ZERO);
+        let now = Instant::now();
+        let parts =
+            queue.push_parts(["1", "2", "3"].map(|part| (text(part), SendDanmuOptions::default())));
+        let other = queue.push(text("other"), SendDanmuOptions::default());
+
+        assert_eq!(queue.next_ready(now).unwrap().id, parts[0]);
+        queue.complete(parts[0], Ok(()), now);
+        assert_eq!(queue.next_ready(now).unwrap().id, parts[1]);
+        queue.complete(parts[1], Err(ApiError::business(1003, "")), now);
+
+        // the third part waits for the second, unrelated messages go on
+        let third = queue.items().find(|item| item.id == parts[2]).unwrap();
+        assert!(queue.is_held(third));
+        assert_eq!(queue.next_ready(now).unwrap().id, other);
+        queue.complete(other, Ok(()), now);
+        assert!(queue.next_ready(now).is_none());
+
+        queue.retry(parts[1]);
+        assert_eq!(queue.next_ready(now).unwrap().id, parts[1]);
+        queue.complete(parts[1], Err(ApiError::business(1003, "")), now);
+
+        // giving up on the second part gives up on the rest of the message
+        assert!(queue.cancel(parts[1]));
+        assert!(queue.is_empty());
+    }
+
+    #[test]
+    fn counts_like_bilibili() {
+        assert_eq!(danmu_length("晚上好"), 3);
+        assert_eq!(danmu_length("hi"), 2);
+        assert_eq!(danmu_length("😀"), 2);
+    }
+
+    #[test]
+    fn splits_at_punctuation() {
+        assert_eq!(
+            split_danmu("今天的直播真好看，明天还会来吗？一定要来哦", 10),
+            vec!["今天的直播真好看，", "明天还会来吗？", "一定要来哦"]
+        );
+        assert_eq!(
+            split_danmu("一二三四五六七", 3),
+            vec!["一二三", "四五六", "七"]
+        );
+        assert_eq!(split_danmu("short", 20), vec!["short"]);
+    }
+
+    #[test]
+    fn keeps_item_in_flight() {
+        let mut queue = SendQueue::default();
+        let id = queue.push(text("1"), SendDanmuOptions::default());
+        queue.next_ready(Instant::now());
+        assert!(!queue.cancel(id));
+    }
+}
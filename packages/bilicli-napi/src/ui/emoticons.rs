@@ -0,0 +1,251 @@
+use std::{collections::HashMap, fs, path::Path};
+
+use ratatui::{prelude::*, style::palette::tailwind};
+
+use crate::{api::EmoticonPackage, paths::config_dir};
+
+use super::Emoticon;
+
+/// the unicode shown for common emoticon tokens
+const BUNDLED_EMOTICONS: &[(&str, &str)] = &[
+    ("[dog]", "🐶"),
+    ("[doge]", "🐶"),
+    ("[手机]", "📱"),
+    ("[花]", "🌹"),
+    ("[吃瓜]", "🍉"),
+    ("[比心]", "❤️"),
+    ("[爱心]", "❤️"),
+    ("[微笑]", "🙂"),
+    ("[OK]", "👌"),
+    ("[星星眼]", "🤩"),
+    ("[笑哭]", "😂"),
+    ("[喜欢]", "😍"),
+    ("[酸了]", "🍋"),
+    ("[大哭]", "😭"),
+    ("[害羞]", "😊"),
+    ("[疑惑]", "🤔"),
+    ("[思考]", "🤔"),
+    ("[无语]", "😑"),
+    ("[生气]", "😠"),
+    ("[捂脸]", "🤦"),
+    ("[哈欠]", "🥱"),
+    ("[惊讶]", "😲"),
+    ("[奸笑]", "😏"),
+    ("[呆]", "😐"),
+    ("[吐]", "🤮"),
+    ("[睡]", "😴"),
+    ("[热]", "🥵"),
+    ("[冷]", "🥶"),
+    ("[鼓掌]", "👏"),
+    ("[点赞]", "👍"),
+    ("[赞]", "👍"),
+    ("[抱拳]", "🙏"),
+    ("[胜利]", "✌️"),
+    ("[再见]", "👋"),
+    ("[加油]", "💪"),
+    ("[打call]", "📣"),
+    ("[干杯]", "🍻"),
+    ("[晚安]", "🌙"),
+    ("[灯笼]", "🏮"),
+    ("[火]", "🔥"),
+];
+
+/// the file mapping emoticon tokens to the text shown for them, e.g. `{"[妙啊]": "👍"}`
+pub const OVERRIDE_FILE: &str = "emoticons.json";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum EmoticonEntry {
+    Unicode(String),
+    /// a room emoticon without unicode equivalent, shown by its description
+    Label(String),
+}
+
+/// how emoticon tokens in danmaku are shown, merged from the bundled mapping, the room's
+/// emoticon packages and the user's override file
+#[derive(Debug, Clone, Default)]
+pub struct EmoticonTable {
+    tokens: HashMap<String, EmoticonEntry>,
+    /// the token of each room emoticon by its `emoticon_unique`
+    uniques: HashMap<String, String>,
+    overrides: HashMap<String, String>,
+}
+
+impl EmoticonTable {
+    /// the bundled mapping with the override file of the user applied
+    pub fn new() -> Self {
+        let mut table = Self::bundled();
+        if let Some(overrides) =
+            config_dir().and_then(|dir| read_overrides(&dir.join(OVERRIDE_FILE)))
+        {
+            table.set_overrides(overrides);
+        }
+
+        table
+    }
+
+    pub fn bundled() -> Self {
+        Self {
+            tokens: BUNDLED_EMOTICONS
+                .iter()
+                .map(|(token, unicode)| {
+                    (
+                        token.to_string(),
+                        EmoticonEntry::Unicode(unicode.to_string()),
+                    )
+                })
+                .collect(),
+            ..Default::default()
+        }
+    }
+
+    /// tokens of the user take precedence over every other mapping
+    pub fn set_overrides(&mut self, overrides: HashMap<String, String>) {
+        self.overrides = overrides;
+    }
+
+    /// learn the emoticons of a room, keeping the unicode of the tokens already known
+    pub fn merge_room(&mut self, packages: &[EmoticonPackage]) {
+        for emoticon in packages.iter().flat_map(|package| &package.emoticons) {
+            self.uniques
+                .insert(emoticon.emoticon_unique.clone(), emoticon.emoji.clone());
+            self.tokens
+                .entry(emoticon.emoji.clone())
+                .or_insert_with(|| {
+                    EmoticonEntry::Label(label(&emoticon.emoji, &emoticon.descript))
+                });
+        }
+    }
+
+    fn lookup(&self, token: &str) -> Option<EmoticonEntry> {
+        match self.overrides.get(token) {
+            Some(text) => Some(EmoticonEntry::Unicode(text.clone())),
+            None => self.tokens.get(token).cloned(),
+        }
+    }
+
+    /// the spans of a danmaku, with `emoticon` set if the whole danmaku is one emoticon
+    pub fn spans(&self, content: &str, emoticon: Option<&Emoticon>) -> Vec<Span<'static>> {
+        if let Some(emoticon) = emoticon {
+            let token = self
+                .uniques
+                .get(&emoticon.id)
+                .map_or(content, String::as_str);
+            return vec![match self.lookup(token) {
+                Some(entry) => entry_span(entry),
+                None => label_span(label(token, "")),
+            }];
+        }
+
+        let mut spans = Vec::new();
+        let mut text = String::new();
+        let mut rest = content;
+        while let Some(start) = rest.find('[') {
+            let Some(len) = rest[start..].find(']') else {
+                break;
+            };
+            let token = &rest[start..=start + len];
+
+            text.push_str(&rest[..start]);
+            match self.lookup(token) {
+                Some(EmoticonEntry::Unicode(unicode)) => text.push_str(&unicode),
+                Some(entry) => {
+                    if !text.is_empty() {
+                        spans.push(Span::from(std::mem::take(&mut text)));
+                    }
+                    spans.push(entry_span(entry));
+                }
+                None => text.push_str(token),
+            }
+            rest = &rest[start + len + 1..];
+        }
+        text.push_str(rest);
+        if !text.is_empty() {
+            spans.push(Span::from(text));
+        }
+
+        spans
+    }
+}
+
+/// the description of a room emoticon, or the name inside the brackets of its token
+fn label(token: &str, descript: &str) -> String {
+    if descript.is_empty() {
+        token
+            .trim_start_matches('[')
+            .trim_end_matches(']')
+            .to_string()
+    } else {
+        descript.to_string()
+    }
+}
+
+fn entry_span(entry: EmoticonEntry) -> Span<'static> {
+    match entry {
+        EmoticonEntry::Unicode(unicode) => Span::from(unicode),
+        EmoticonEntry::Label(label) => label_span(label),
+    }
+}
+
+fn label_span(label: String) -> Span<'static> {
+    Span::from(format!("[{}]", label))
+        .fg(tailwind::SKY.c300)
+        .italic()
+}
+
+fn read_overrides(path: &Path) -> Option<HashMap<String, String>> {
+    serde_json::from_slice(&fs::read(path).ok()?).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn packages() -> Vec<EmoticonPackage> {
+        serde_json::from_str(
+            r#"[{"pkg_id":1,"pkg_name":"通用表情","pkg_type":1,"emoticons":[
+                {"emoji":"[dog]","descript":"","url":"","emoticon_unique":"emoji_1","perm":1},
+                {"emoji":"[妙啊]","descript":"","url":"","emoticon_unique":"emoji_2","perm":1},
+                {"emoji":"[豆豆_开心]","descript":"开心","url":"","emoticon_unique":"room_1_1","perm":1}
+            ]}]"#,
+        )
+        .unwrap()
+    }
+
+    fn text(spans: &[Span]) -> String {
+        spans.iter().map(|span| span.content.as_ref()).collect()
+    }
+
+    #[test]
+    fn maps_tokens_by_precedence() {
+        let mut table = EmoticonTable::bundled();
+        table.merge_room(&packages());
+
+        let spans = table.spans("好[dog][妙啊]![未知]", None);
+        assert_eq!(text(&spans), "好🐶[妙啊]![未知]");
+        assert_eq!(spans.len(), 3);
+        assert_eq!(spans[1].style.fg, Some(tailwind::SKY.c300));
+
+        table.set_overrides(HashMap::from([("[妙啊]".to_string(), "👍".to_string())]));
+        assert_eq!(text(&table.spans("[妙啊][dog", None)), "👍[dog");
+    }
+
+    #[test]
+    fn uses_attached_emoticon() {
+        let mut table = EmoticonTable::bundled();
+        table.merge_room(&packages());
+
+        let emoticon = Emoticon {
+            id: "room_1_1".to_string(),
+            height: 60,
+            width: 60,
+            url: String::new(),
+        };
+        assert_eq!(text(&table.spans("开心", Some(&emoticon))), "[开心]");
+
+        let emoticon = Emoticon {
+            id: "official_1".to_string(),
+            ..emoticon
+        };
+        assert_eq!(text(&table.spans("[赞]", Some(&emoticon))), "👍");
+    }
+}
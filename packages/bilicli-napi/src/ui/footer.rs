@@ -21,14 +21,13 @@ impl StatefulWidget for &mut Footer {
             .messages
             .iter()
             .filter(|(t, _)| *t == MsgType::UserAction)
-            .filter(|(_, msg)| {
+            .rfind(|(_, msg)| {
                 if let Ok(msg) = serde_json::from_str::<UserActionMsg>(msg) {
                     msg.action.as_str() == "enter"
                 } else {
                     false
                 }
-            })
-            .last();
+            });
 
         let enter_text = {
             if let Some(enter) = enter {
@@ -0,0 +1,140 @@
+use ratatui::{
+    prelude::*,
+    style::palette::tailwind,
+    widgets::{Block, Clear, List, ListItem, ListState, Padding, Paragraph},
+};
+
+use crate::{api::GuardMember, TuiState};
+
+use super::helper::centered_rect;
+
+/// the popup listing every guard of the room, filtered by `query`
+#[derive(Debug, Default)]
+pub struct GuardRoster {
+    pub query: String,
+    /// whether key presses edit `query`
+    pub searching: bool,
+    pub state: ListState,
+}
+
+impl GuardRoster {
+    pub fn open(&mut self) {
+        self.query.clear();
+        self.searching = false;
+        self.state.select_first();
+    }
+
+    /// the guards whose name or uid contains `query`
+    pub fn filtered<'a>(&self, guards: &'a [GuardMember]) -> Vec<&'a GuardMember> {
+        let query = self.query.trim().to_lowercase();
+        guards
+            .iter()
+            .filter(|guard| {
+                query.is_empty()
+                    || guard.username.to_lowercase().contains(&query)
+                    || guard.uid.to_string().contains(&query)
+            })
+            .collect()
+    }
+
+    pub fn push_query(&mut self, c: char) {
+        self.query.push(c);
+        self.state.select_first();
+    }
+
+    pub fn pop_query(&mut self) {
+        self.query.pop();
+        self.state.select_first();
+    }
+}
+
+fn guard_name(level: u8) -> (&'static str, Color) {
+    match level {
+        1 => ("总督", tailwind::RED.c400),
+        2 => ("提督", tailwind::PURPLE.c400),
+        _ => ("舰长", tailwind::SKY.c400),
+    }
+}
+
+impl StatefulWidget for &mut GuardRoster {
+    type State = TuiState;
+
+    fn render(self, area: Rect, buf: &mut Buffer, state: &mut Self::State) {
+        let area = centered_rect(70, 70, area);
+        let title = match &state.guards {
+            Some(guards) => format!(" 大航海 (共 {} 人) ", guards.len()),
+            None => " 大航海 ".to_string(),
+        };
+        let search = if self.searching || !self.query.is_empty() {
+            format!(
+                " 搜索: {}{} ",
+                self.query,
+                if self.searching { "_" } else { "" }
+            )
+        } else {
+            " / 搜索, r 刷新, Esc 关闭 ".to_string()
+        };
+        let block = Block::bordered()
+            .title(title)
+            .title_bottom(Line::from(search).centered())
+            .title_alignment(Alignment::Center)
+            .border_set(symbols::border::ROUNDED)
+            .border_style(Style::default().fg(tailwind::SKY.c400))
+            .padding(Padding::horizontal(1));
+
+        Clear.render(area, buf);
+
+        let Some(guards) = &state.guards else {
+            Paragraph::new("正在加载...").block(block).render(area, buf);
+            return;
+        };
+        let guards = self.filtered(guards);
+        if guards.is_empty() {
+            Paragraph::new("没有匹配的舰长")
+                .block(block)
+                .render(area, buf);
+            return;
+        }
+
+        let items: Vec<ListItem> = guards
+            .iter()
+            .map(|guard| {
+                let (name, color) = guard_name(guard.guard_level);
+                let online = if guard.is_online() {
+                    Span::from("● ").fg(tailwind::GREEN.c400)
+                } else {
+                    Span::from("○ ").fg(Color::DarkGray)
+                };
+                let mut spans = vec![
+                    Span::from(format!("{:>4} ", guard.rank)).fg(Color::DarkGray),
+                    online,
+                    Span::from(format!(" {} ", name))
+                        .fg(tailwind::BLACK)
+                        .bg(color),
+                    Span::raw(" "),
+                ];
+                if !guard.medal_info.medal_name.is_empty() {
+                    spans.push(Span::from(format!(
+                        "{} {} ",
+                        guard.medal_info.medal_name, guard.medal_info.medal_level
+                    )));
+                }
+                spans.push(Span::from(guard.username.clone()).bold().fg(color));
+                spans.push(Span::from(format!(" (UID {})", guard.uid)).fg(Color::DarkGray));
+
+                ListItem::new(Line::from(spans))
+            })
+            .collect();
+
+        let list = List::new(items)
+            .block(block)
+            .highlight_style(
+                Style::default()
+                    .bg(tailwind::YELLOW.c300)
+                    .fg(tailwind::BLACK),
+            )
+            .highlight_spacing(ratatui::widgets::HighlightSpacing::WhenSelected);
+
+        StatefulWidget::render(list, area, buf, &mut self.state);
+    }
+}
@@ -5,6 +5,8 @@ use ratatui::{
 
 use crate::TuiState;
 
+use super::{ConnectionState, RoomInfoState};
+
 #[derive(Default, Debug)]
 pub struct Header;
 
@@ -16,18 +18,63 @@ impl StatefulWidget for &Header {
             .border_type(ratatui::widgets::BorderType::Rounded)
             .padding(Padding::horizontal(1));
 
-        Paragraph::new(
-            Line::from(vec![
-                Span::from(format!("{}", state.room_id)).fg(Color::Green),
-                Span::raw(" "),
-                Span::from(format!("({}·{})", state.area_name, state.parent_area_name))
-                    .fg(Color::LightGreen),
-                Span::raw(" "),
-                Span::from(state.title.clone()),
-            ])
-            .bold(),
-        )
-        .block(block)
-        .render(area, buf);
+        let room = if state.room_resolved {
+            state.room_id.to_string()
+        } else {
+            state.room_query.to_string()
+        };
+        let mut spans = vec![Span::from(room).fg(Color::Green)];
+        if state.short_id != 0 && state.short_id != state.room_id {
+            spans.push(Span::from(format!("(短号 {})", state.short_id)).fg(Color::DarkGray));
+        }
+        spans.push(Span::raw(" "));
+
+        match &state.room_info {
+            RoomInfoState::Loading => {
+                spans.push(Span::from("正在获取直播间信息...").fg(Color::DarkGray));
+            }
+            RoomInfoState::Failed { loaded: false, .. } => {
+                spans.push(Span::from("(未知分区)").fg(Color::LightGreen));
+                spans.push(Span::raw(" "));
+                spans.push(Span::from("直播间信息获取失败").fg(Color::Red));
+            }
+            RoomInfoState::Loaded | RoomInfoState::Failed { loaded: true, .. } => {
+                spans.push(
+                    Span::from(format!("({}·{})", state.area_name, state.parent_area_name))
+                        .fg(Color::LightGreen),
+                );
+                spans.push(Span::raw(" "));
+                spans.push(Span::from(state.title.clone()));
+            }
+        }
+
+        if let RoomInfoState::Failed { loaded: true, .. } = state.room_info {
+            spans.push(Span::raw(" "));
+            spans.push(Span::from("[信息刷新失败]").fg(Color::Red));
+        }
+
+        if let Some(err) = &state.proxy_error {
+            spans.push(Span::raw(" "));
+            spans.push(Span::from(format!("[代理不可用, 已停止联网: {}]", err)).fg(Color::Red));
+        }
+
+        if let ConnectionState::Reconnecting { attempt } = state.connection {
+            spans.push(Span::raw(" "));
+            let reason = match &state.connection_error {
+                Some(err) => format!(": {}", err),
+                None => String::new(),
+            };
+            spans.push(
+                Span::from(format!(
+                    "[弹幕连接已断开, 第 {} 次重连中{}]",
+                    attempt, reason
+                ))
+                .fg(Color::Red),
+            );
+        }
+
+        Paragraph::new(Line::from(spans).bold())
+            .block(block)
+            .render(area, buf);
     }
 }
@@ -3,8 +3,14 @@ use std::str::FromStr;
 use chrono::prelude::*;
 use ratatui::{prelude::*, style::palette::tailwind};
 
+use crate::{
+    api::GiftConfig,
+    gift_catalog::{gift_icon, GiftValue},
+};
+
 use super::{
     colors::{GRAY_COLOR, USER_COLORS},
+    emoticons::EmoticonTable,
     DanmuMsg, GiftMsg, GuardBuyMsg, MsgType, SuperChatMsg, User, UserActionMsg,
 };
 
@@ -25,16 +31,6 @@ pub fn centered_rect(percent_x: u16, percent_y: u16, r: Rect) -> Rect {
     .split(popup_layout[1])[1]
 }
 
-/// replace emoji to unicode
-pub fn replace_emoji_to_unicode(emoji_str: &str) -> String {
-    emoji_str
-        .replace("[dog]", "🐶")
-        .replace("[手机]", "📱")
-        .replace("[花]", "🌹")
-        .replace("[吃瓜]", "🍉")
-        .replace("[比心]", "❤️")
-}
-
 pub fn render_basic_info(
     t: Option<MsgType>,
     user: User,
@@ -96,6 +92,7 @@ pub fn render_basic_info(
 /// render danmu message
 pub fn render_danmu_message(
     msg: DanmuMsg,
+    emoticons: &EmoticonTable,
     time: DateTime<Local>,
     render_type: bool,
 ) -> Line<'static> {
@@ -104,7 +101,16 @@ pub fn render_danmu_message(
         msg.user,
         Some(time),
     );
-    spans.push(Span::from(replace_emoji_to_unicode(&msg.content)));
+    let content = emoticons.spans(&msg.content, msg.emoticon.as_ref());
+    if msg.history {
+        spans.push(Span::from("[历史] ").fg(Color::DarkGray));
+        spans.extend(content.into_iter().map(|span| span.dim()));
+    } else if msg.pending {
+        spans.extend(content.into_iter().map(|span| span.dim().italic()));
+        spans.push(Span::from(" (等待确认)").fg(Color::DarkGray));
+    } else {
+        spans.extend(content);
+    }
 
     Line::from(spans)
 }
@@ -128,8 +134,11 @@ pub fn render_super_chat_message(
 }
 
 /// render gift message
+///
+/// `gift` is the catalog entry of the gift, which takes precedence over the data of the message
 pub fn render_gift_message(
     msg: GiftMsg,
+    gift: Option<&GiftConfig>,
     time: DateTime<Local>,
     render_type: bool,
 ) -> Line<'static> {
@@ -138,18 +147,25 @@ pub fn render_gift_message(
         msg.user,
         Some(time),
     );
+
+    let (name, coin_type, price) = match gift {
+        Some(gift) => (gift.name.as_str(), gift.coin_type.as_str(), gift.price),
+        None => (msg.gift_name.as_str(), msg.coin_type.as_str(), msg.price),
+    };
     spans.push(Span::from(format!(
-        "赠送了{} * {} ",
-        msg.gift_name, msg.amount
+        "赠送了{} {} * {} ",
+        gift_icon(name),
+        name,
+        msg.amount
     )));
 
-    let total = (msg.price * msg.amount) as f32 / 1000.0;
-    if total > 0.0 {
-        spans.push(
-            Span::from(format!("({:.1} 元)", total))
-                .fg(Color::LightMagenta)
-                .bold(),
-        );
+    let value = GiftValue::new(coin_type, price, msg.amount);
+    if let Some(text) = value.text() {
+        let color = match value {
+            GiftValue::Silver(_) => Color::Gray,
+            _ => Color::LightMagenta,
+        };
+        spans.push(Span::from(format!("({})", text)).fg(color).bold());
     }
 
     if let Some(master) = msg.send_master {
@@ -202,6 +218,18 @@ pub fn render_user_action_message(
     Line::from(spans)
 }
 
+/// render the marker of a gap while the danmaku connection was down
+pub fn render_disconnect_message(start: DateTime<Local>, end: DateTime<Local>) -> Line<'static> {
+    Line::from(format!(
+        "—— 弹幕连接中断 {} ~ {}, 期间的消息可能已丢失 ——",
+        start.format("%H:%M:%S"),
+        end.format("%H:%M:%S")
+    ))
+    .fg(tailwind::RED.c400)
+    .bold()
+    .centered()
+}
+
 #[inline]
 fn get_msg_type(render_type: bool, msg_type: MsgType) -> Option<MsgType> {
     if render_type {
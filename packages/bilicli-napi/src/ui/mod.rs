@@ -1,14 +1,21 @@
 #![allow(dead_code)]
 
+use std::fmt;
+
 #[cfg(feature = "platform-napi")]
 use napi_derive::napi;
 use serde::{Deserialize, Serialize};
 use strum::{Display, EnumIter, FromRepr};
 
 pub mod colors;
+pub mod emoticons;
 pub mod footer;
+pub mod guards;
 pub mod header;
 pub mod helper;
+pub mod moderation;
+pub mod profile;
+pub mod room_editor;
 pub mod tabs;
 
 #[cfg(feature = "platform-napi")]
@@ -35,6 +42,70 @@ pub enum InputMode {
     #[default]
     Normal,
     Editing,
+    /// the emoticon picker is open on top of the input box
+    PickingEmoticon,
+    /// the send queue is focused to cancel or retry items
+    ManagingQueue,
+    /// a message of the current tab is selected to act on it, e.g. to reply
+    SelectingMessage,
+    /// choosing how long to mute the sender of the selected message
+    Muting,
+    /// the list of muted viewers is open
+    ViewingMuted,
+    /// the anchor is editing the title and area of the room
+    EditingRoom,
+    /// the guard roster is open
+    ViewingGuards,
+    /// the profile of the sender of the selected message is open
+    ViewingProfile,
+}
+
+/// the state of the native danmaku connection, `Idle` when events are pushed from outside
+#[derive(Default, PartialEq, Eq, Clone, Copy, Debug)]
+pub enum ConnectionState {
+    #[default]
+    Idle,
+    Connected,
+    Reconnecting {
+        attempt: u32,
+    },
+}
+
+#[derive(Default, PartialEq, Eq, Clone, Debug)]
+pub enum RoomInfoState {
+    #[default]
+    Loading,
+    Loaded,
+    /// the last fetch failed; `loaded` tells whether an earlier fetch succeeded
+    Failed {
+        message: String,
+        loaded: bool,
+    },
+}
+
+/// how the user named the room to open
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub enum RoomQuery {
+    /// a short id, long id or room url
+    Room(String),
+    StreamerUid(u64),
+    StreamerName(String),
+}
+
+impl Default for RoomQuery {
+    fn default() -> Self {
+        RoomQuery::Room(String::new())
+    }
+}
+
+impl fmt::Display for RoomQuery {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RoomQuery::Room(room) => write!(f, "{}", room),
+            RoomQuery::StreamerUid(uid) => write!(f, "UID {}", uid),
+            RoomQuery::StreamerName(name) => write!(f, "{}", name),
+        }
+    }
 }
 
 #[derive(Default, PartialEq, Eq, Clone, Copy, Debug)]
@@ -58,6 +129,8 @@ pub enum MsgType {
     GuardBuy,
     #[strum(to_string = "进场")]
     UserAction,
+    #[strum(to_string = "断线")]
+    Disconnect,
 }
 
 #[cfg(not(feature = "platform-napi"))]
@@ -73,21 +146,23 @@ pub enum MsgType {
     GuardBuy,
     #[strum(to_string = "进场")]
     UserAction,
+    #[strum(to_string = "断线")]
+    Disconnect,
 }
 
 #[derive(Deserialize, Serialize)]
 pub struct Anchor {
-    uid: u64,
-    uname: String,
-    room_id: u32,
-    is_same_room: Option<bool>,
+    pub(crate) uid: u64,
+    pub(crate) uname: String,
+    pub(crate) room_id: u32,
+    pub(crate) is_same_room: Option<bool>,
 }
 
 #[derive(Deserialize, Serialize)]
 pub struct Identity {
-    rank: u8,
-    guard_level: u8,
-    room_admin: bool,
+    pub(crate) rank: u8,
+    pub(crate) guard_level: u8,
+    pub(crate) room_admin: bool,
 }
 
 #[derive(Deserialize, Serialize)]
@@ -132,10 +207,10 @@ pub struct User {
 
 #[derive(Deserialize, Serialize)]
 pub struct Emoticon {
-    id: String,
-    height: i32,
-    width: i32,
-    url: String,
+    pub(crate) id: String,
+    pub(crate) height: i32,
+    pub(crate) width: i32,
+    pub(crate) url: String,
 }
 
 #[derive(Deserialize, Serialize)]
@@ -145,9 +220,32 @@ pub struct DanmuMsg {
     timestamp: i64,
     lottery: bool,
     emoticon: Option<Emoticon>,
+    /// sent before we joined, loaded from the room history
+    #[serde(default)]
+    history: bool,
+    /// our own danmaku, shown before it comes back from the live stream
+    #[serde(default)]
+    pending: bool,
 }
 
 impl DanmuMsg {
+    pub fn user(&self) -> &User {
+        &self.user
+    }
+
+    pub fn into_user(self) -> User {
+        self.user
+    }
+
+    pub fn content(&self) -> &str {
+        &self.content
+    }
+
+    /// set when the whole danmaku is one emoticon
+    pub fn emoticon(&self) -> Option<&Emoticon> {
+        self.emoticon.as_ref()
+    }
+
     pub fn new(
         user: User,
         content: String,
@@ -161,63 +259,94 @@ impl DanmuMsg {
             timestamp,
             lottery,
             emoticon,
+            history: false,
+            pending: false,
+        }
+    }
+
+    pub fn is_pending(&self) -> bool {
+        self.pending
+    }
+
+    pub fn into_pending(self) -> Self {
+        Self {
+            pending: true,
+            ..self
+        }
+    }
+
+    pub fn is_history(&self) -> bool {
+        self.history
+    }
+
+    pub fn into_history(self) -> Self {
+        Self {
+            history: true,
+            ..self
         }
     }
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, Serialize)]
 pub struct GuardBuyMsg {
-    timestamp: i64,
-    user: User,
-    gift_id: u32,
-    gift_name: String,
-    guard_level: u8,
-    price: u32,
-    start_time: u32,
-    end_time: u32,
+    pub(crate) timestamp: i64,
+    pub(crate) user: User,
+    pub(crate) gift_id: u32,
+    pub(crate) gift_name: String,
+    pub(crate) guard_level: u8,
+    pub(crate) price: u32,
+    pub(crate) start_time: u32,
+    pub(crate) end_time: u32,
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, Serialize)]
 pub struct SuperChatMsg {
-    id: u64,
-    user: User,
-    content: String,
-    content_color: String,
-    price: u32,
-    time: u32,
-    timestamp: i64,
+    pub(crate) id: u64,
+    pub(crate) user: User,
+    pub(crate) content: String,
+    pub(crate) content_color: String,
+    pub(crate) price: u32,
+    pub(crate) time: u32,
+    pub(crate) timestamp: i64,
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, Serialize)]
 pub struct Master {
-    uid: u64,
-    uname: String,
-    room_id: u32,
+    pub(crate) uid: u64,
+    pub(crate) uname: String,
+    pub(crate) room_id: u32,
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, Serialize)]
 pub struct Combo {
-    batch_id: String,
-    combo_num: u32,
-    total_price: u32,
+    pub(crate) batch_id: String,
+    pub(crate) combo_num: u32,
+    pub(crate) total_price: u32,
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, Serialize)]
 pub struct GiftMsg {
-    timestamp: i64,
-    user: User,
-    gift_id: u32,
-    gift_name: String,
-    coin_type: String,
-    price: u32,
-    amount: u32,
-    send_master: Option<Master>,
-    combo: Option<Combo>,
+    pub(crate) timestamp: i64,
+    pub(crate) user: User,
+    pub(crate) gift_id: u32,
+    pub(crate) gift_name: String,
+    pub(crate) coin_type: String,
+    pub(crate) price: u32,
+    pub(crate) amount: u32,
+    pub(crate) send_master: Option<Master>,
+    pub(crate) combo: Option<Combo>,
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, Serialize)]
 pub struct UserActionMsg {
-    user: User,
-    action: String,
-    timestamp: i64,
+    pub(crate) user: User,
+    pub(crate) action: String,
+    pub(crate) timestamp: i64,
+}
+
+/// a gap in the message list while the danmaku connection was down
+#[derive(Deserialize, Serialize)]
+pub struct DisconnectMsg {
+    pub(crate) start: i64,
+    pub(crate) end: i64,
 }
@@ -0,0 +1,145 @@
+use ratatui::{
+    prelude::*,
+    style::palette::tailwind,
+    widgets::{Block, Clear, List, ListItem, ListState, Padding, Paragraph},
+};
+
+use crate::api::{MuteDuration, SilentUser};
+
+use super::helper::centered_rect;
+
+/// the durations offered when muting a viewer
+pub const MUTE_DURATIONS: [MuteDuration; 5] = [
+    MuteDuration::CurrentLive,
+    MuteDuration::Hours(1),
+    MuteDuration::Hours(24),
+    MuteDuration::Hours(24 * 7),
+    MuteDuration::Forever,
+];
+
+/// the viewer a moderation action is about
+#[derive(Debug, Clone)]
+pub struct ModerationTarget {
+    pub uid: u64,
+    pub uname: String,
+    /// the danmaku the viewer was selected by
+    pub msg: String,
+}
+
+/// the popup to choose how long to mute `target`
+#[derive(Debug, Default)]
+pub struct MuteMenu {
+    pub target: Option<ModerationTarget>,
+    pub state: ListState,
+}
+
+impl MuteMenu {
+    pub fn open(&mut self, target: ModerationTarget) {
+        self.target = Some(target);
+        self.state.select_first();
+    }
+
+    pub fn selected(&self) -> Option<MuteDuration> {
+        MUTE_DURATIONS.get(self.state.selected()?).copied()
+    }
+}
+
+impl Widget for &mut MuteMenu {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let Some(target) = &self.target else {
+            return;
+        };
+
+        let area = centered_rect(40, 40, area);
+        let block = Block::bordered()
+            .title(format!(" 禁言 {} ", target.uname))
+            .title_bottom(Line::from(" Enter 确认, Esc 取消 ").centered())
+            .title_alignment(Alignment::Center)
+            .border_set(symbols::border::ROUNDED)
+            .border_style(Style::default().fg(tailwind::RED.c400))
+            .padding(Padding::horizontal(1));
+
+        let items: Vec<ListItem> = MUTE_DURATIONS
+            .iter()
+            .map(|duration| ListItem::new(duration.name()))
+            .collect();
+        let list = List::new(items)
+            .block(block)
+            .highlight_style(
+                Style::default()
+                    .bg(tailwind::YELLOW.c300)
+                    .fg(tailwind::BLACK),
+            )
+            .highlight_spacing(ratatui::widgets::HighlightSpacing::WhenSelected);
+
+        Clear.render(area, buf);
+        StatefulWidget::render(list, area, buf, &mut self.state);
+    }
+}
+
+/// the popup listing the viewers muted in the room
+#[derive(Debug, Default)]
+pub struct MutedUsersPanel {
+    /// `None` while loading
+    pub users: Option<Vec<SilentUser>>,
+    pub total: u32,
+    pub state: ListState,
+}
+
+impl MutedUsersPanel {
+    pub fn selected(&self) -> Option<&SilentUser> {
+        self.users.as_ref()?.get(self.state.selected()?)
+    }
+}
+
+impl Widget for &mut MutedUsersPanel {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let area = centered_rect(70, 60, area);
+        let block = Block::bordered()
+            .title(format!(" 禁言列表 (共 {} 人) ", self.total))
+            .title_bottom(Line::from(" x 解除禁言, r 刷新, Esc 关闭 ").centered())
+            .title_alignment(Alignment::Center)
+            .border_set(symbols::border::ROUNDED)
+            .border_style(Style::default().fg(tailwind::ORANGE.c400))
+            .padding(Padding::horizontal(1));
+
+        Clear.render(area, buf);
+
+        let Some(users) = &self.users else {
+            Paragraph::new("正在加载...").block(block).render(area, buf);
+            return;
+        };
+        if users.is_empty() {
+            Paragraph::new("没有被禁言的用户")
+                .block(block)
+                .render(area, buf);
+            return;
+        }
+
+        let items: Vec<ListItem> = users
+            .iter()
+            .map(|user| {
+                ListItem::new(Line::from(vec![
+                    Span::from(user.tname.clone()).bold(),
+                    Span::from(format!(" (UID {})", user.tuid)).fg(Color::DarkGray),
+                    Span::raw(format!("  {} 由 {} 禁言", user.ctime, user.name)),
+                ]))
+            })
+            .collect();
+
+        if self.state.selected().is_none() {
+            self.state.select_first();
+        }
+
+        let list = List::new(items)
+            .block(block)
+            .highlight_style(
+                Style::default()
+                    .bg(tailwind::YELLOW.c300)
+                    .fg(tailwind::BLACK),
+            )
+            .highlight_spacing(ratatui::widgets::HighlightSpacing::WhenSelected);
+
+        StatefulWidget::render(list, area, buf, &mut self.state);
+    }
+}
@@ -0,0 +1,121 @@
+use ratatui::{
+    prelude::*,
+    style::palette::tailwind,
+    widgets::{Block, Clear, Padding, Paragraph, Wrap},
+};
+
+use crate::api::UserCard;
+
+use super::{helper::centered_rect, User};
+
+/// the popup with the profile of the sender of the selected message
+#[derive(Default)]
+pub struct ProfilePopup {
+    pub user: Option<User>,
+    /// the danmaku the viewer sent since we joined
+    pub message_count: usize,
+    /// `None` while loading, the error text if the card could not be fetched
+    pub card: Option<Result<UserCard, String>>,
+}
+
+impl std::fmt::Debug for ProfilePopup {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ProfilePopup")
+            .field("uid", &self.user.as_ref().map(|user| user.uid))
+            .field("message_count", &self.message_count)
+            .finish()
+    }
+}
+
+impl ProfilePopup {
+    pub fn open(&mut self, user: User, message_count: usize) {
+        self.user = Some(user);
+        self.message_count = message_count;
+        self.card = None;
+    }
+
+    pub fn uid(&self) -> Option<u64> {
+        self.user.as_ref().map(|user| user.uid)
+    }
+}
+
+fn field(name: &'static str, value: impl Into<Span<'static>>) -> Line<'static> {
+    Line::from(vec![
+        Span::from(format!("{:<8}", name)).fg(Color::DarkGray),
+        value.into(),
+    ])
+}
+
+impl Widget for &mut ProfilePopup {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let Some(user) = &self.user else {
+            return;
+        };
+
+        let area = centered_rect(50, 50, area);
+        let block = Block::bordered()
+            .title(format!(" {} ", user.uname))
+            .title_bottom(Line::from(" Esc 关闭 ").centered())
+            .title_alignment(Alignment::Center)
+            .border_set(symbols::border::ROUNDED)
+            .border_style(Style::default().fg(tailwind::SKY.c400))
+            .padding(Padding::uniform(1));
+
+        let mut lines = vec![field("UID", user.uid.to_string())];
+
+        match &self.card {
+            None => lines.push(field("资料", Span::from("正在加载...").fg(Color::DarkGray))),
+            Some(Err(err)) => lines.push(field("资料", Span::from(err.clone()).red())),
+            Some(Ok(card)) => {
+                lines.push(field(
+                    "等级",
+                    Span::from(format!("LV{}", card.card.level_info.current_level))
+                        .fg(tailwind::ORANGE.c400)
+                        .bold(),
+                ));
+                lines.push(field("粉丝", card.follower.to_string()));
+                if !card.card.sign.is_empty() {
+                    lines.push(field("签名", card.card.sign.clone()));
+                }
+            }
+        }
+
+        match &user.badge {
+            Some(badge) => {
+                let mut medal = format!("{} {} 级", badge.name, badge.level);
+                if let Some(anchor) = badge
+                    .anchor
+                    .as_ref()
+                    .filter(|anchor| !anchor.uname.is_empty())
+                {
+                    medal.push_str(&format!(" (主播 {})", anchor.uname));
+                }
+                lines.push(field("粉丝牌", medal));
+            }
+            None => lines.push(field("粉丝牌", Span::from("未佩戴").fg(Color::DarkGray))),
+        }
+
+        if let Some(identity) = &user.identity {
+            let guard = match identity.guard_level {
+                1 => "总督",
+                2 => "提督",
+                3 => "舰长",
+                _ => "",
+            };
+            if !guard.is_empty() {
+                lines.push(field("大航海", guard));
+            }
+            if identity.room_admin {
+                lines.push(field("身份", "房管"));
+            }
+        }
+
+        lines.push(field("本场弹幕", format!("{} 条", self.message_count)));
+
+        Clear.render(area, buf);
+        Paragraph::new(lines)
+            .block(block)
+            .wrap(Wrap { trim: true })
+            .render(area, buf);
+    }
+}
@@ -0,0 +1,234 @@
+use crossterm::event::KeyEvent;
+use ratatui::{
+    prelude::*,
+    style::palette::tailwind,
+    widgets::{Block, Borders, Clear, List, ListItem, ListState, Padding, Paragraph},
+};
+use tui_textarea::TextArea;
+
+use crate::{
+    api::{Area, AreaParent},
+    TuiState,
+};
+
+use super::helper::centered_rect;
+
+/// the longest title the room update API accepts
+pub const MAX_TITLE_LENGTH: usize = 40;
+
+/// the part of the room editor receiving key presses
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum RoomEditorFocus {
+    #[default]
+    Title,
+    ParentArea,
+    Area,
+}
+
+/// the anchor-only dialog to change the title and the area of the room
+#[derive(Debug, Default)]
+pub struct RoomEditor {
+    pub title: TextArea<'static>,
+    /// `None` while loading
+    pub areas: Option<Vec<AreaParent>>,
+    pub parent_state: ListState,
+    pub area_state: ListState,
+    pub focus: RoomEditorFocus,
+}
+
+impl RoomEditor {
+    /// start editing with the title and area currently shown in the header
+    pub fn open(&mut self, state: &TuiState) {
+        self.title = TextArea::new(vec![state.title.clone()]);
+        self.title.move_cursor(tui_textarea::CursorMove::End);
+        self.focus = RoomEditorFocus::Title;
+        self.select_current(state);
+    }
+
+    pub fn set_areas(&mut self, areas: Vec<AreaParent>, state: &TuiState) {
+        self.areas = Some(areas);
+        self.select_current(state);
+    }
+
+    /// select the parent area and sub-area the room is currently in
+    fn select_current(&mut self, state: &TuiState) {
+        let Some(areas) = &self.areas else {
+            return;
+        };
+
+        let parent = areas
+            .iter()
+            .position(|parent| parent.name == state.parent_area_name);
+        let area = parent.and_then(|parent| {
+            areas[parent]
+                .list
+                .iter()
+                .position(|area| area.name == state.area_name)
+        });
+
+        self.parent_state.select(parent.or(Some(0)));
+        self.area_state.select(area.or(Some(0)));
+    }
+
+    pub fn next_focus(&mut self) {
+        self.focus = match self.focus {
+            RoomEditorFocus::Title => RoomEditorFocus::ParentArea,
+            RoomEditorFocus::ParentArea => RoomEditorFocus::Area,
+            RoomEditorFocus::Area => RoomEditorFocus::Title,
+        };
+    }
+
+    pub fn select_previous(&mut self) {
+        match self.focus {
+            RoomEditorFocus::Title => {}
+            RoomEditorFocus::ParentArea => {
+                self.parent_state.select_previous();
+                self.area_state.select_first();
+            }
+            RoomEditorFocus::Area => self.area_state.select_previous(),
+        }
+    }
+
+    pub fn select_next(&mut self) {
+        match self.focus {
+            RoomEditorFocus::Title => {}
+            RoomEditorFocus::ParentArea => {
+                let len = self.areas.as_ref().map_or(0, Vec::len);
+                if self
+                    .parent_state
+                    .selected()
+                    .is_some_and(|index| index + 1 < len)
+                {
+                    self.parent_state.select_next();
+                    self.area_state.select_first();
+                }
+            }
+            RoomEditorFocus::Area => self.area_state.select_next(),
+        }
+    }
+
+    /// type into the title, keeping it within `MAX_TITLE_LENGTH`
+    pub fn input(&mut self, key: KeyEvent) {
+        if self.focus == RoomEditorFocus::Title && self.title.input(key) {
+            let title = self.title_text();
+            if title.chars().count() > MAX_TITLE_LENGTH {
+                self.title = TextArea::new(vec![title.chars().take(MAX_TITLE_LENGTH).collect()]);
+                self.title.move_cursor(tui_textarea::CursorMove::End);
+            }
+        }
+    }
+
+    pub fn title_text(&self) -> String {
+        self.title.lines()[0].trim().to_string()
+    }
+
+    fn selected_parent(&self) -> Option<&AreaParent> {
+        self.areas.as_ref()?.get(self.parent_state.selected()?)
+    }
+
+    pub fn selected_area(&self) -> Option<&Area> {
+        let parent = self.selected_parent()?;
+        parent.list.get(
+            self.area_state
+                .selected()?
+                .min(parent.list.len().checked_sub(1)?),
+        )
+    }
+
+    fn border_style(&self, focus: RoomEditorFocus) -> Style {
+        if self.focus == focus {
+            Style::default().fg(tailwind::ORANGE.c400)
+        } else {
+            Style::default().fg(Color::DarkGray)
+        }
+    }
+}
+
+impl Widget for &mut RoomEditor {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let area = centered_rect(60, 60, area);
+        let block = Block::bordered()
+            .title(" 编辑直播间 ")
+            .title_bottom(Line::from(" Tab 切换, Enter 保存, Esc 取消 ").centered())
+            .title_alignment(Alignment::Center)
+            .border_set(symbols::border::ROUNDED)
+            .border_style(Style::default().fg(tailwind::ORANGE.c400))
+            .padding(Padding::horizontal(1));
+        let inner = block.inner(area);
+
+        Clear.render(area, buf);
+        block.render(area, buf);
+
+        let [title_area, areas_area] =
+            Layout::vertical([Constraint::Length(3), Constraint::Fill(1)]).areas(inner);
+        let [parent_area, sub_area] =
+            Layout::horizontal([Constraint::Percentage(40), Constraint::Percentage(60)])
+                .areas(areas_area);
+
+        self.title.set_cursor_line_style(Style::default());
+        self.title
+            .set_cursor_style(if self.focus == RoomEditorFocus::Title {
+                Style::default().add_modifier(Modifier::REVERSED)
+            } else {
+                Style::default()
+            });
+        self.title.set_block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_set(symbols::border::ROUNDED)
+                .border_style(self.border_style(RoomEditorFocus::Title))
+                .title(format!(
+                    " 标题 {} / {} ",
+                    self.title.lines()[0].chars().count(),
+                    MAX_TITLE_LENGTH
+                )),
+        );
+        self.title.render(title_area, buf);
+
+        let Some(areas) = &self.areas else {
+            Paragraph::new("正在加载分区...").render(areas_area, buf);
+            return;
+        };
+
+        let highlight_style = Style::default()
+            .bg(tailwind::YELLOW.c300)
+            .fg(tailwind::BLACK);
+
+        let parents: Vec<ListItem> = areas
+            .iter()
+            .map(|parent| ListItem::new(parent.name.clone()))
+            .collect();
+        let subs: Vec<ListItem> = self
+            .parent_state
+            .selected()
+            .and_then(|index| areas.get(index))
+            .map(|parent| {
+                parent
+                    .list
+                    .iter()
+                    .map(|area| ListItem::new(area.name.clone()))
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        let parent_list = List::new(parents)
+            .block(
+                Block::bordered()
+                    .title(" 分区 ")
+                    .border_set(symbols::border::ROUNDED)
+                    .border_style(self.border_style(RoomEditorFocus::ParentArea)),
+            )
+            .highlight_style(highlight_style);
+        let sub_list = List::new(subs)
+            .block(
+                Block::bordered()
+                    .title(" 子分区 ")
+                    .border_set(symbols::border::ROUNDED)
+                    .border_style(self.border_style(RoomEditorFocus::Area)),
+            )
+            .highlight_style(highlight_style);
+
+        StatefulWidget::render(parent_list, parent_area, buf, &mut self.parent_state);
+        StatefulWidget::render(sub_list, sub_area, buf, &mut self.area_state);
+    }
+}
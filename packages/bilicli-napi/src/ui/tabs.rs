@@ -3,17 +3,19 @@ use std::fmt;
 use chrono::{DateTime, Local, TimeZone, Utc};
 use ratatui::{
     prelude::*,
+    style::palette::tailwind,
     widgets::{block::Title, Block, ListState, Padding, Paragraph, Scrollbar, ScrollbarState},
 };
 
-use crate::TuiState;
+use crate::{api::OnlineRankUser, TuiState};
 
 use super::{
     helper::{
-        render_danmu_message, render_gift_message, render_guard_buy_message,
-        render_super_chat_message, render_user_action_message,
+        render_basic_info, render_danmu_message, render_disconnect_message, render_gift_message,
+        render_guard_buy_message, render_super_chat_message, render_user_action_message,
     },
-    DanmuMsg, GiftMsg, GuardBuyMsg, MsgType, SliderBarState, SuperChatMsg, UserActionMsg,
+    Anchor, Badge, DanmuMsg, DisconnectMsg, GiftMsg, GuardBuyMsg, Identity, MsgType,
+    SliderBarState, SuperChatMsg, User, UserActionMsg,
 };
 
 #[derive(Clone)]
@@ -24,6 +26,8 @@ pub enum Tab {
     GiftTab(usize, ScrollbarState),
     CaptainTab(usize, ScrollbarState),
     EnterTab(usize, ScrollbarState),
+    /// the online gold rank, not built from messages
+    RankTab(usize, ScrollbarState),
 }
 
 impl Tab {
@@ -35,6 +39,7 @@ impl Tab {
             Tab::GiftTab(_, _) => "礼物".to_string(),
             Tab::CaptainTab(_, _) => "上舰".to_string(),
             Tab::EnterTab(_, _) => "入场".to_string(),
+            Tab::RankTab(_, _) => "高能".to_string(),
         }
     }
 
@@ -64,6 +69,10 @@ impl Tab {
                 *scroll = scroll.saturating_sub(1);
                 *state = state.position(*scroll);
             }
+            Tab::RankTab(scroll, state) => {
+                *scroll = scroll.saturating_sub(1);
+                *state = state.position(*scroll);
+            }
         };
     }
 
@@ -93,6 +102,10 @@ impl Tab {
                 *scroll = scroll.saturating_add(1);
                 *state = state.position(*scroll);
             }
+            Tab::RankTab(scroll, state) => {
+                *scroll = scroll.saturating_add(1);
+                *state = state.position(*scroll);
+            }
         };
     }
 
@@ -104,6 +117,7 @@ impl Tab {
             Tab::GiftTab(scroll, _) => *scroll,
             Tab::CaptainTab(scroll, _) => *scroll,
             Tab::EnterTab(scroll, _) => *scroll,
+            Tab::RankTab(scroll, _) => *scroll,
         }
     }
 
@@ -115,6 +129,7 @@ impl Tab {
             Tab::GiftTab(_, state) => *state = state.content_length(content_length),
             Tab::CaptainTab(_, state) => *state = state.content_length(content_length),
             Tab::EnterTab(_, state) => *state = state.content_length(content_length),
+            Tab::RankTab(_, state) => *state = state.content_length(content_length),
         };
     }
 
@@ -136,6 +151,9 @@ impl Tab {
             Tab::EnterTab(_, state) => {
                 *state = state.viewport_content_length(viewport_content_length)
             }
+            Tab::RankTab(_, state) => {
+                *state = state.viewport_content_length(viewport_content_length)
+            }
         };
     }
 
@@ -147,6 +165,7 @@ impl Tab {
             Tab::GiftTab(_, state) => state,
             Tab::CaptainTab(_, state) => state,
             Tab::EnterTab(_, state) => state,
+            Tab::RankTab(_, state) => state,
         }
     }
 }
@@ -162,11 +181,56 @@ impl StatefulWidget for &mut Tab {
             Tab::GiftTab(_, _) => self.render_gift_tab(area, buf, state),
             Tab::CaptainTab(_, _) => self.render_captain_tab(area, buf, state),
             Tab::EnterTab(_, _) => self.render_enter_tab(area, buf, state),
+            Tab::RankTab(_, _) => self.render_rank_tab(area, buf, state),
         }
     }
 }
 
 impl Tab {
+    /// whether messages of type `t` are shown in this tab
+    pub fn accepts(&self, t: MsgType) -> bool {
+        match self {
+            Tab::AllTab(_, _) => t != MsgType::UserAction,
+            Tab::DanMuTab(_, _) => t == MsgType::Danmu || t == MsgType::Disconnect,
+            Tab::SCTab(_, _) => t == MsgType::SuperChat || t == MsgType::Disconnect,
+            Tab::GiftTab(_, _) => t == MsgType::Gift || t == MsgType::Disconnect,
+            Tab::CaptainTab(_, _) => t == MsgType::GuardBuy || t == MsgType::Disconnect,
+            Tab::EnterTab(_, _) => t == MsgType::UserAction || t == MsgType::Disconnect,
+            Tab::RankTab(_, _) => false,
+        }
+    }
+
+    /// the rendered messages of this tab, and the line of the selected message if shown
+    fn lines(&self, state: &TuiState, render_type: bool) -> (Vec<Line<'static>>, Option<usize>) {
+        let mut selected = None;
+        let lines = state
+            .messages
+            .iter()
+            .enumerate()
+            .filter(|(_, (t, _))| self.accepts(*t))
+            .enumerate()
+            .map(|(line, (index, (t, b)))| {
+                let msg = Self::render_msg(*t, b.clone(), render_type, state);
+                if state.selected_message == Some(index) {
+                    selected = Some(line);
+                    msg.reversed()
+                } else {
+                    msg
+                }
+            })
+            .collect();
+
+        (lines, selected)
+    }
+
+    fn scroll_to_selected(&mut self, selected: Option<usize>) {
+        if let Some(selected) = selected {
+            while self.scroll() > selected {
+                self.scroll_up();
+            }
+        }
+    }
+
     fn should_scroll_down(&mut self, content_length: usize, area: &Rect) -> bool {
         self.scroll() + area.height as usize - 2 < content_length
     }
@@ -175,7 +239,7 @@ impl Tab {
         self.scroll() > 0 && area.height > content_length as u16
     }
 
-    fn block(&self, state: &TuiState) -> Block {
+    fn block(&self, state: &TuiState) -> Block<'_> {
         Block::bordered()
             .title({
                 if state.slider_bar_state == SliderBarState::Hiding {
@@ -196,14 +260,10 @@ impl Tab {
     }
 
     fn render_all_tab(&mut self, area: Rect, buf: &mut Buffer, state: &mut TuiState) {
-        let text: Vec<Line<'static>> = state
-            .messages
-            .iter()
-            .filter(|(t, _)| *t != MsgType::UserAction)
-            .map(|(t, b)| Self::render_msg(*t, b.clone(), true))
-            .collect();
+        let (text, selected) = self.lines(state, true);
 
         self.setup_scrollbar(text.len(), area);
+        self.scroll_to_selected(selected);
 
         Paragraph::new(text)
             .block(self.block(state))
@@ -218,14 +278,10 @@ impl Tab {
     }
 
     fn render_danmu_tab(&mut self, area: Rect, buf: &mut Buffer, state: &mut TuiState) {
-        let text: Vec<Line<'static>> = state
-            .messages
-            .iter()
-            .filter(|(t, _)| *t == MsgType::Danmu)
-            .map(|(t, b)| Self::render_msg(*t, b.clone(), false))
-            .collect();
+        let (text, selected) = self.lines(state, false);
 
         self.setup_scrollbar(text.len(), area);
+        self.scroll_to_selected(selected);
 
         Paragraph::new(text)
             .block(self.block(state))
@@ -240,14 +296,10 @@ impl Tab {
     }
 
     fn render_sc_tab(&mut self, area: Rect, buf: &mut Buffer, state: &mut TuiState) {
-        let text: Vec<Line<'static>> = state
-            .messages
-            .iter()
-            .filter(|(t, _)| *t == MsgType::SuperChat)
-            .map(|(t, b)| Self::render_msg(*t, b.clone(), false))
-            .collect();
+        let (text, selected) = self.lines(state, false);
 
         self.setup_scrollbar(text.len(), area);
+        self.scroll_to_selected(selected);
 
         Paragraph::new(text)
             .block(self.block(state))
@@ -262,14 +314,10 @@ impl Tab {
     }
 
     fn render_gift_tab(&mut self, area: Rect, buf: &mut Buffer, state: &mut TuiState) {
-        let text: Vec<Line<'static>> = state
-            .messages
-            .iter()
-            .filter(|(t, _)| *t == MsgType::Gift)
-            .map(|(t, b)| Self::render_msg(*t, b.clone(), false))
-            .collect();
+        let (text, selected) = self.lines(state, false);
 
         self.setup_scrollbar(text.len(), area);
+        self.scroll_to_selected(selected);
 
         Paragraph::new(text)
             .block(self.block(state))
@@ -284,14 +332,10 @@ impl Tab {
     }
 
     fn render_captain_tab(&mut self, area: Rect, buf: &mut Buffer, state: &mut TuiState) {
-        let text: Vec<Line<'static>> = state
-            .messages
-            .iter()
-            .filter(|(t, _)| *t == MsgType::GuardBuy)
-            .map(|(t, b)| Self::render_msg(*t, b.clone(), false))
-            .collect();
+        let (text, selected) = self.lines(state, false);
 
         self.setup_scrollbar(text.len(), area);
+        self.scroll_to_selected(selected);
 
         Paragraph::new(text)
             .block(self.block(state))
@@ -306,14 +350,10 @@ impl Tab {
     }
 
     fn render_enter_tab(&mut self, area: Rect, buf: &mut Buffer, state: &mut TuiState) {
-        let text: Vec<Line<'static>> = state
-            .messages
-            .iter()
-            .filter(|(t, _)| *t == MsgType::UserAction)
-            .map(|(t, b)| Self::render_msg(*t, b.clone(), false))
-            .collect();
+        let (text, selected) = self.lines(state, false);
 
         self.setup_scrollbar(text.len(), area);
+        self.scroll_to_selected(selected);
 
         Paragraph::new(text)
             .block(self.block(state))
@@ -329,13 +369,42 @@ impl Tab {
 }
 
 impl Tab {
-    fn render_msg(t: MsgType, b: String, render_type: bool) -> Line<'static> {
+    fn render_rank_tab(&mut self, area: Rect, buf: &mut Buffer, state: &mut TuiState) {
+        let text: Vec<Line> = if state.online_rank.is_empty() {
+            vec![Line::from("暂无高能用户").fg(Color::DarkGray)]
+        } else {
+            state
+                .online_rank
+                .iter()
+                .map(|user| render_rank_user(user, state.streamer_uid))
+                .collect()
+        };
+
+        // the rank is read from the top, unlike the message tabs following the latest line
+        self.set_state_content_length(text.len());
+
+        let block = self.block(state).title_bottom(
+            Line::from(format!(" 高能用户 {} 人 ", state.online_rank_count)).centered(),
+        );
+        Paragraph::new(text)
+            .block(block)
+            .scroll((self.scroll() as u16, 0))
+            .render(area, buf);
+
+        let scrollbar = Scrollbar::new(ratatui::widgets::ScrollbarOrientation::VerticalRight)
+            .begin_symbol(Some("w"))
+            .end_symbol(Some("s"));
+
+        StatefulWidget::render(scrollbar, area, buf, self.state());
+    }
+
+    fn render_msg(t: MsgType, b: String, render_type: bool, state: &TuiState) -> Line<'static> {
         match t {
             MsgType::Danmu => {
                 if let Ok(msg) = serde_json::from_str::<DanmuMsg>(&b) {
                     let time = get_local_time_from_timestamp(msg.timestamp);
 
-                    render_danmu_message(msg, time, render_type)
+                    render_danmu_message(msg, &state.emoticon_table, time, render_type)
                 } else {
                     Line::from(vec![Span::from("解析弹幕消息失败")])
                         .red()
@@ -355,7 +424,8 @@ impl Tab {
                 if let Ok(msg) = serde_json::from_str::<GiftMsg>(&b) {
                     let time = get_local_time_from_timestamp(msg.timestamp);
 
-                    render_gift_message(msg, time, render_type)
+                    let gift = state.gift_catalog.get(msg.gift_id);
+                    render_gift_message(msg, gift, time, render_type)
                 } else {
                     Line::from(vec![Span::from("解析礼物消息失败")])
                         .red()
@@ -384,6 +454,18 @@ impl Tab {
                         .bold()
                 }
             }
+            MsgType::Disconnect => {
+                if let Ok(msg) = serde_json::from_str::<DisconnectMsg>(&b) {
+                    let start = get_local_time_from_timestamp(msg.start);
+                    let end = get_local_time_from_timestamp(msg.end);
+
+                    render_disconnect_message(start, end)
+                } else {
+                    Line::from(vec![Span::from("解析断线消息失败")])
+                        .red()
+                        .bold()
+                }
+            }
         }
     }
 }
@@ -394,6 +476,10 @@ pub struct Tabs {
 }
 
 impl Tabs {
+    pub fn selected(&self) -> Option<&Tab> {
+        self.tabs.get(self.state.selected()?)
+    }
+
     pub fn next_tab(&mut self) {
         let selected = self.state.selected().unwrap();
         if selected + 1 < self.tabs.len() {
@@ -432,12 +518,65 @@ impl Default for Tabs {
                 Tab::GiftTab(0, ScrollbarState::default()),
                 Tab::CaptainTab(0, ScrollbarState::default()),
                 Tab::EnterTab(0, ScrollbarState::default()),
+                Tab::RankTab(0, ScrollbarState::default()),
             ],
             state,
         }
     }
 }
 
+/// a line of the online rank: the rank, the medal and name of the viewer and the score
+fn render_rank_user(user: &OnlineRankUser, streamer_uid: u64) -> Line<'static> {
+    let badge = user
+        .medal_info
+        .as_ref()
+        .filter(|medal| !medal.medal_name.is_empty() && medal.level > 0)
+        .map(|medal| {
+            Badge::new(
+                medal.medal_name.clone(),
+                medal.level,
+                format!("#{:06X}", medal.medal_color_start),
+                Some(vec![
+                    format!("#{:06X}", medal.medal_color_start),
+                    format!("#{:06X}", medal.medal_color_end),
+                ]),
+                Some(Anchor {
+                    uid: medal.target_id,
+                    uname: String::new(),
+                    room_id: 0,
+                    is_same_room: Some(medal.target_id == streamer_uid),
+                }),
+                None,
+            )
+        });
+    let viewer = User {
+        uid: user.uid,
+        uname: user.name.clone(),
+        face: None,
+        badge,
+        identity: Some(Identity {
+            rank: 0,
+            guard_level: user.guard_level,
+            room_admin: false,
+        }),
+    };
+
+    let rank_color = match user.user_rank {
+        1 => tailwind::AMBER.c400,
+        2 => tailwind::SLATE.c300,
+        3 => tailwind::ORANGE.c600,
+        _ => tailwind::GRAY.c500,
+    };
+
+    let mut spans = vec![Span::from(format!("{:>3}", user.user_rank))
+        .fg(rank_color)
+        .bold()];
+    spans.extend(render_basic_info(None, viewer, None));
+    spans.push(Span::from(format!("{} 贡献", user.score)).fg(Color::LightMagenta));
+
+    Line::from(spans)
+}
+
 fn get_local_time_from_timestamp(timestamp: i64) -> DateTime<Local> {
     let time = DateTime::from_timestamp_millis(timestamp).unwrap_or(Utc::now());
     let time = time.naive_local();
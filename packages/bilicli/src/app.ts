@@ -10,6 +10,11 @@ export interface AppOptions {
   cookie?: string
   config?: string
   uid?: string
+  streamerUid?: string
+  streamer?: string
+  maxLength?: string
+  split?: boolean
+  proxy?: string
 }
 
 export interface EditOptions {
@@ -17,11 +22,14 @@ export interface EditOptions {
 }
 
 export class App {
-  private readonly roomId: number
   private readonly tui: Tui
+  private readonly handler: MsgHandler
+  private readonly uid?: number
 
   constructor(roomId: string | undefined, private options: AppOptions) {
-    if (!roomId) {
+    const byStreamer = !!(this.options.streamerUid || this.options.streamer)
+
+    if (!roomId && !byStreamer) {
       const config = getConfigPath(this.options.config)
 
       if (!existsSync(config)) {
@@ -35,11 +43,12 @@ export class App {
         roomId = c.roomId
         this.options.cookie = c?.cookie || ''
         this.options.uid = c?.uid
+        this.options.proxy = this.options.proxy || c?.proxy
       } catch (error) {
         console.error(error)
         throw new Error('配置文件格式不正确')
       }
-    } else if(!this.options?.cookie || !this.options?.uid) {
+    } else if(!this.options?.cookie || !this.options?.uid || !this.options?.proxy) {
       const config = getConfigPath(this.options.config)
       if (existsSync(config)) {
         try {
@@ -54,20 +63,29 @@ export class App {
           if(!this.options.uid && c.uid) {
             this.options.uid = c.uid
           }
+          if(!this.options.proxy && c.proxy) {
+            this.options.proxy = c.proxy
+          }
         } catch {
         }
       }
     }
 
-    this.roomId = parseInt(roomId)
-
-    if (isNaN(this.roomId)) {
+    if (this.options.streamerUid) {
+      const streamerUid = parseInt(this.options.streamerUid)
+      if (isNaN(streamerUid)) {
+        throw new Error('主播 UID 格式不正确')
+      }
+      this.tui = Tui.withStreamerUid(streamerUid, this.options?.cookie)
+    } else if (this.options.streamer) {
+      this.tui = Tui.withStreamerName(String(this.options.streamer), this.options?.cookie)
+    } else if (roomId) {
+      this.tui = new Tui(String(roomId), this.options?.cookie)
+    } else {
       throw new Error('房间号格式不正确')
     }
 
-    this.tui = new Tui(this.roomId, this.options?.cookie)
-
-    const handler: MsgHandler = {
+    this.handler = {
       onAttentionChange: ({ body }) => this.tui.sendAttentionChange(body.attention),
       onWatchedChange: ({ body }) => this.tui.sendWatcherChange(body.num),
       onLiveStart: () => this.tui.sendLiveChange(true),
@@ -79,27 +97,49 @@ export class App {
       onUserAction: (msg) => this.tui.sendMsg(MsgType.UserAction, JSON.stringify(this.mixTimestamp2Body(msg))),
     }
 
-    let uid: number | undefined
     if (this.options?.uid) {
-      uid = parseInt(this.options.uid)
-      if (isNaN(uid)) {
+      this.uid = parseInt(this.options.uid)
+      if (isNaN(this.uid)) {
         throw new Error('UID 格式不正确')
       }
     }
-
-    startListen(this.roomId, handler, {
-      ws: {
-        headers: {
-          'Cookie': this.options?.cookie || "",
-        },
-        uid,
-      },
-    })
   }
 
   async run() {
+    if (this.options.maxLength) {
+      const maxLength = parseInt(this.options.maxLength)
+      if (isNaN(maxLength)) {
+        throw new Error('弹幕长度格式不正确')
+      }
+      await this.tui.setInputLimit(maxLength)
+    }
+    if (this.options.split) {
+      await this.tui.setSplitLongDanmu(true)
+    }
+    await this.tui.setProxy(this.options.proxy)
+    // the message listener cannot go through a proxy, so the native connection takes over
+    const proxied = (await this.tui.proxy) !== null
+
+    const running = this.tui.run()
+
+    // the room may only be known after the user picked a streamer or retried in the tui
+    this.tui.waitRoom().then((roomId) => {
+      if (roomId !== null && proxied) {
+        this.tui.listen(this.uid)
+      } else if (roomId !== null) {
+        startListen(roomId, this.handler, {
+          ws: {
+            headers: {
+              'Cookie': this.options?.cookie || "",
+            },
+            uid: this.uid,
+          },
+        })
+      }
+    })
+
     try {
-      await this.tui.run()
+      await running
     } catch {
       restoreTerminal()
     }
@@ -157,6 +197,7 @@ export async function openEditor(config: string) {
 module.exports = {
   cookie: '',
   uid: undefined,
+  proxy: undefined,
   roomId: 1,
 }
     `)
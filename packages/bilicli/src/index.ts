@@ -7,9 +7,14 @@ import { App, AppOptions, EditOptions, openEditor } from './app'
 const cli = cac('bilicli')
 
 cli
-  .command('[room_id]', '输入房间号，打开直播间控制台')
+  .command('[room_id]', '输入房间号 (短号、长号、直播间链接或主播名)，打开直播间控制台')
   .option('--cookie <cookie>', '将你在B站登录的cookie粘贴到这里')
   .option('--uid <uid>', '你的B站UID')
+  .option('--streamer-uid <uid>', '通过主播的UID打开其直播间')
+  .option('--streamer <name>', '通过主播名搜索直播间, 有多个结果时在界面中选择')
+  .option('--max-length <length>', '单条弹幕的最大长度, 默认按账号等级')
+  .option('--split', '超出长度的弹幕自动拆分为多条发送')
+  .option('--proxy <url>', '代理地址 (http://, https://, socks5:// 或 socks5h://), 默认读取 ALL_PROXY / HTTPS_PROXY / HTTP_PROXY 并遵循 NO_PROXY')
   .option('--config [config]', '配置文件路径', { default: 'bilicli.config.js' })
   .action(async (roomId: string | undefined, options: AppOptions) => {
     const app = new App(roomId, options)